@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde_yaml::{Mapping, Value};
+use crate::utils::app_config::{get, join_path, AppConfig, ConfigFiles};
+use crate::utils::docker_compose::DockerCompose;
+use crate::utils::general::{ConfigCommands, ConfigFormat};
+use crate::utils::yaml_edit;
+
+pub fn run(config_files: &ConfigFiles, project_root: Option<&Path>, command: ConfigCommands) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        ConfigCommands::Show { origin, format } => {
+            let (value, origins) = resolve(config_files);
+            match (format, origin) {
+                (ConfigFormat::Yaml, false) => print!("{}", serde_yaml::to_string(&value)?),
+                (ConfigFormat::Yaml, true) => print_with_origins(&value, &origins, "", 0),
+                (ConfigFormat::Json, false) => println!("{}", serde_json::to_string_pretty(&value)?),
+                (ConfigFormat::Json, true) => println!("{}", serde_json::to_string_pretty(&with_origins(&value, &origins, ""))?),
+            }
+        }
+        ConfigCommands::Get { key } => {
+            let (value, _) = resolve(config_files);
+            match get(&value, &key) {
+                Some(Value::String(value)) => println!("{}", value),
+                Some(value) => print!("{}", serde_yaml::to_string(value)?),
+                None => {
+                    eprintln!("The key '{}' is not set", key);
+                    sysexits::ExitCode::DataErr.exit()
+                }
+            }
+        }
+        ConfigCommands::Set { key, value, local: _, project, global } => {
+            let file = match (global, project) {
+                (true, _) => Some(&config_files.global),
+                (_, true) => config_files.project.as_ref(),
+                _ => config_files.local.as_ref(),
+            };
+            let file = match file {
+                Some(file) => file,
+                None => {
+                    eprintln!("Could not find a project root, use --global to change the global config");
+                    sysexits::ExitCode::Usage.exit()
+                }
+            };
+
+            // Values are parsed like in the config files, so lists etc. work too
+            let value = serde_yaml::from_str(&value).unwrap_or(Value::String(value));
+            let content = if file.is_file() { std::fs::read_to_string(file)? } else { String::new() };
+            let path: Vec<&str> = key.split('.').collect();
+            let content = yaml_edit::set(&content, &path, &value);
+
+            // Don't write a config that can't be loaded anymore
+            if let Err(error) = serde_yaml::from_str::<AppConfig>(&content) {
+                eprintln!("Could not set '{}' in {} ({})", key, file.display(), error);
+                sysexits::ExitCode::DataErr.exit()
+            }
+            if let Some(directory) = file.parent() {
+                std::fs::create_dir_all(directory)?;
+            }
+            std::fs::write(file, content)?;
+            eprintln!("Set '{}' in {}", key, file.display());
+        }
+        ConfigCommands::Schema => {
+            println!("{}", serde_json::to_string_pretty(&schemars::schema_for!(AppConfig))?);
+        }
+        ConfigCommands::Compose { compare } => {
+            let project_root = match project_root {
+                Some(project_root) => project_root,
+                None => {
+                    eprintln!("Could not find a project root");
+                    sysexits::ExitCode::OsErr.exit()
+                }
+            };
+            let app_config = match AppConfig::load(&config_files.all()) {
+                Ok(app_config) => app_config,
+                Err(error) => {
+                    eprintln!("{:#}", error);
+                    sysexits::ExitCode::Config.exit()
+                }
+            };
+            let docker_compose = match DockerCompose::from_config(project_root, &app_config) {
+                Ok(docker_compose) => docker_compose,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    sysexits::ExitCode::OsErr.exit()
+                }
+            };
+
+            let native = match docker_compose.native_config() {
+                Ok(native) => native,
+                Err(error) => {
+                    eprintln!("Could not read the compose files ({})", error);
+                    sysexits::ExitCode::DataErr.exit()
+                }
+            };
+            if !compare {
+                print!("{}", serde_yaml::to_string(&native)?);
+                return Ok(());
+            }
+
+            let cli = match docker_compose.cli_config() {
+                Ok(cli) => cli,
+                Err(error) => {
+                    eprintln!("Could not run docker compose config ({})", error);
+                    sysexits::ExitCode::Unavailable.exit()
+                }
+            };
+            if !print_differences(&cli, &native) {
+                eprintln!("No differences to docker compose config");
+                return Ok(());
+            }
+            // Like diff, differences are reported with exit code 1
+            std::process::exit(1)
+        }
+        ConfigCommands::Validate { files } => {
+            let result = if files.is_empty() {
+                AppConfig::load(&config_files.all()).map(|_| ())
+            } else {
+                files.iter().try_for_each(|file| AppConfig::parse_file(file).map(|_| ()))
+            };
+            if let Err(error) = result {
+                eprintln!("{:#}", error);
+                sysexits::ExitCode::Config.exit()
+            }
+            eprintln!("The config is valid");
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve(config_files: &ConfigFiles) -> (Value, BTreeMap<String, String>) {
+    match AppConfig::resolve(&config_files.all()) {
+        Ok(resolved) => resolved,
+        Err(error) => {
+            eprintln!("{:#}", error);
+            sysexits::ExitCode::Config.exit()
+        }
+    }
+}
+
+/// Prints the config as YAML with a comment naming the origin after every value.
+fn print_with_origins(value: &Value, origins: &BTreeMap<String, String>, prefix: &str, indent: usize) {
+    let Value::Mapping(mapping) = value else { return };
+    let padding = " ".repeat(indent);
+    for (key, value) in mapping {
+        let key = key.as_str().unwrap_or_default();
+        let path = join_path(prefix, key);
+        let origin = origins.get(&path).map(String::as_str).unwrap_or_default();
+        match value {
+            Value::Mapping(mapping) if !mapping.is_empty() => {
+                println!("{}{}:", padding, key);
+                print_with_origins(value, origins, &path, indent + 2);
+            }
+            Value::Sequence(sequence) if !sequence.is_empty() => {
+                println!("{}{}:  # {}", padding, key, origin);
+                for line in serde_yaml::to_string(value).unwrap_or_default().lines() {
+                    println!("{}  {}", padding, line);
+                }
+            }
+            _ => println!("{}{}: {}  # {}", padding, key, serde_yaml::to_string(value).unwrap_or_default().trim_end(), origin),
+        }
+    }
+}
+
+/// Replaces every value with an object holding the value and its origin.
+fn with_origins(value: &Value, origins: &BTreeMap<String, String>, prefix: &str) -> Value {
+    match value {
+        Value::Mapping(mapping) if !mapping.is_empty() => Value::Mapping(
+            mapping
+                .iter()
+                .map(|(key, value)| (key.clone(), with_origins(value, origins, &join_path(prefix, key.as_str().unwrap_or_default()))))
+                .collect(),
+        ),
+        _ => {
+            let mut annotated = Mapping::new();
+            annotated.insert(Value::from("value"), value.clone());
+            annotated.insert(Value::from("origin"), Value::from(origins.get(prefix).cloned().unwrap_or_default()));
+            Value::Mapping(annotated)
+        }
+    }
+}
+
+/// Prints the values that differ between the output of `docker compose config`
+/// and the native loader, by their path. Returns whether there were any.
+fn print_differences(cli: &Value, native: &Value) -> bool {
+    let (mut cli_values, mut native_values) = (BTreeMap::new(), BTreeMap::new());
+    flatten(cli, "", &mut cli_values);
+    flatten(native, "", &mut native_values);
+
+    let mut paths: Vec<&String> = cli_values.keys().chain(native_values.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut different = false;
+    for path in paths {
+        let (cli_value, native_value) = (cli_values.get(path), native_values.get(path));
+        if cli_value == native_value {
+            continue;
+        }
+        if !different {
+            println!("--- docker compose config");
+            println!("+++ dev-cli");
+            different = true;
+        }
+        if let Some(cli_value) = cli_value {
+            println!("- {}: {}", path, cli_value);
+        }
+        if let Some(native_value) = native_value {
+            println!("+ {}: {}", path, native_value);
+        }
+    }
+    different
+}
+
+/// Collects all scalars by their path, list items are addressed by their index.
+fn flatten(value: &Value, prefix: &str, values: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Mapping(mapping) if !mapping.is_empty() => {
+            for (key, value) in mapping {
+                let key = key.as_str().map(String::from).unwrap_or(serde_yaml::to_string(key).unwrap_or_default().trim().to_string());
+                flatten(value, &join_path(prefix, &key), values);
+            }
+        }
+        Value::Sequence(sequence) if !sequence.is_empty() => {
+            for (index, value) in sequence.iter().enumerate() {
+                flatten(value, &join_path(prefix, &index.to_string()), values);
+            }
+        }
+        _ => {
+            values.insert(prefix.to_string(), serde_yaml::to_string(value).unwrap_or_default().trim().to_string());
+        }
+    }
+}
@@ -1,5 +1,86 @@
-use crate::utils::docker_compose::DockerCompose;
+use bollard::Docker;
+use std::io::IsTerminal;
+use std::time::Duration;
+use crate::utils::app_config::{AppConfig, ExecWhenStopped};
+use crate::utils::container::{exec, project_containers};
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::exit_code;
+use crate::utils::readiness::wait_until_ready;
 
-pub fn run(docker_compose: DockerCompose, service: Option<String>, user: Option<String>, command: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    docker_compose.exec(service, user, command)
+/// How many seconds to wait for a stopped service to become ready, like `dev-cli start`
+const START_TIMEOUT: u64 = 120;
+
+pub struct ExecArgs {
+    pub service: Option<String>,
+    pub user: Option<String>,
+    pub env: Vec<(String, String)>,
+    pub command: Vec<String>,
+}
+
+pub async fn run(docker: &Docker, docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, args: ExecArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let ExecArgs { service, user, env, command } = args;
+    let service = match config.resolve_service(app_config, service.as_deref()) {
+        Ok(service) => service,
+        Err(error) => {
+            eprintln!("{}", error);
+            sysexits::ExitCode::Usage.exit()
+        }
+    };
+
+    // The flags override the defaults of the service from the config
+    let service_config = app_config.service(&service);
+    let user = user.or_else(|| service_config.and_then(|service_config| service_config.user.clone()));
+    let workdir = config.workdir(&service, service_config.and_then(|service_config| service_config.workdir.as_deref()));
+    let env = app_config.service_env(&service, env);
+
+    let container = match running_container(docker, config, &service).await? {
+        Some(container) => container,
+        None => match app_config.exec_when_stopped.unwrap_or(ExecWhenStopped::Error) {
+            ExecWhenStopped::Error => {
+                eprintln!("The service '{}' is not running, start it with `dev-cli start`", service);
+                sysexits::ExitCode::Unavailable.exit()
+            }
+            ExecWhenStopped::Start => {
+                eprintln!("The service '{}' is not running, starting it and the services it depends on", service);
+                let services = config.with_dependencies(std::slice::from_ref(&service));
+                docker_compose.up(std::slice::from_ref(&service))?;
+                wait_until_ready(docker, config, &services, Duration::from_secs(START_TIMEOUT)).await?;
+                match running_container(docker, config, &service).await? {
+                    Some(container) => container,
+                    None => {
+                        eprintln!("The service '{}' stopped right after starting", service);
+                        sysexits::ExitCode::Unavailable.exit()
+                    }
+                }
+            }
+            ExecWhenStopped::Run => {
+                eprintln!("The service '{}' is not running, running the command in a new container", service);
+                let tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+                let status = docker_compose
+                    .run_command(&service, user.as_deref(), workdir.as_deref(), &env, tty)
+                    .args(&command)
+                    .join()?;
+                if !status.success() {
+                    std::process::exit(exit_code(status))
+                }
+                return Ok(());
+            }
+        },
+    };
+
+    let exit_code = exec(docker, &container, command, user.as_deref(), workdir.as_deref(), &env).await?;
+    if exit_code != 0 {
+        std::process::exit(exit_code as i32)
+    }
+
+    Ok(())
+}
+
+/// The ID of a running container of the service
+async fn running_container(docker: &Docker, config: &Config, service: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let containers = project_containers(docker, &config.name, Some(service)).await?;
+    Ok(containers
+        .into_iter()
+        .find(|container| container.state.as_deref() == Some("running"))
+        .map(|container| container.id.unwrap_or_default()))
 }
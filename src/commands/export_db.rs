@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::utils::app_config::AppConfig;
+use crate::utils::compression::{CompressedWriter, Compression};
+use crate::utils::database::Database;
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::exit_code;
+
+pub fn run(docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, project_root: &Path, file: Option<String>, compression: Option<Compression>) -> Result<(), Box<dyn std::error::Error>> {
+    let database = match Database::from_config(config, app_config) {
+        Ok(database) => database,
+        Err(error) => {
+            eprintln!("{}", error);
+            sysexits::ExitCode::Config.exit()
+        }
+    };
+
+    let destination = match file.as_deref() {
+        Some("-") => None,
+        file => Some(destination(config, app_config, project_root, file, compression)),
+    };
+
+    match &destination {
+        Some(path) => {
+            if let Some(directory) = path.parent() {
+                std::fs::create_dir_all(directory)?;
+            }
+            eprintln!("Exporting the {} database of '{}' to {}...", database.engine, database.service, path.display());
+            let compression = Compression::from_path(path);
+            export(&docker_compose, &database, compression.writer(File::create(path)?)?, path)
+        }
+        None => {
+            let compression = compression.unwrap_or(Compression::None);
+            export(&docker_compose, &database, compression.writer(std::io::stdout())?, Path::new("-"))
+        }
+    }
+}
+
+/// Determines the file to write to. Relative paths are relative to the dumps
+/// directory, without a file name one is generated from the current time.
+fn destination(config: &Config, app_config: &AppConfig, project_root: &Path, file: Option<&str>, compression: Option<Compression>) -> PathBuf {
+    let mut path = match file {
+        Some(file) => app_config.dumps_dir(project_root).join(file),
+        None => app_config.dumps_dir(project_root).join(format!(
+            "{}-{}.sql",
+            config.name,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        )),
+    };
+
+    if let Some(compression) = compression {
+        if Compression::from_path(&path) != compression {
+            path = PathBuf::from(format!("{}{}", path.display(), compression.extension()));
+        }
+    }
+    path
+}
+
+/// Streams the dump into the writer, on failure a partially written file is removed.
+pub fn export<W: Write + 'static>(docker_compose: &DockerCompose, database: &Database, mut writer: CompressedWriter<W>, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut process = docker_compose
+        .exec_command(&database.service, None, None, &database.environment(), false)
+        .args(&database.dump_command())
+        .stdout(subprocess::Redirection::Pipe)
+        .popen()?;
+
+    let mut output = process.stdout.take().unwrap();
+    let copied = std::io::copy(&mut output, &mut writer).and_then(|_| writer.finish().map(|_| ()));
+    let status = process.wait()?;
+
+    if copied.is_err() || !status.success() {
+        if path.is_file() {
+            std::fs::remove_file(path)?;
+        }
+        if let Err(error) = copied {
+            eprintln!("Could not write the dump ({})", error);
+            sysexits::ExitCode::IoErr.exit()
+        }
+        eprintln!("Dumping the database failed ({:?})", status);
+        std::process::exit(exit_code(status))
+    }
+
+    if path != Path::new("-") {
+        eprintln!("Exported the database to {}", path.display());
+    }
+    Ok(())
+}
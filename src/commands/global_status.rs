@@ -0,0 +1,58 @@
+use bollard::Docker;
+use bollard::container::ListContainersOptions;
+use std::collections::HashMap;
+use std::path::Path;
+use crate::utils::registry::Registry;
+use crate::utils::table::print_table;
+use crate::utils::time::ago;
+
+/// Label docker compose puts on containers with the directory of the project
+const LABEL_WORKING_DIR: &str = "com.docker.compose.project.working_dir";
+
+pub async fn run(docker: &Docker, registry_file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut registry = Registry::load(registry_file)?;
+    let removed = registry.prune();
+    if !removed.is_empty() {
+        registry.save(registry_file)?;
+        for project in removed {
+            println!("Forgot project '{}', {} does not exist anymore", project.name, project.path.display());
+        }
+    }
+
+    if registry.projects.is_empty() {
+        println!("No projects have been run through dev-cli yet");
+        return Ok(());
+    }
+
+    let mut rows = Vec::new();
+    registry.projects.sort_by_key(|project| std::cmp::Reverse(project.last_used));
+    for project in &registry.projects {
+        let mut filters = HashMap::new();
+        filters.insert(String::from("label"), vec![format!("{}={}", LABEL_WORKING_DIR, project.path.display())]);
+        let containers = docker.list_containers(Some(ListContainersOptions {
+            all: true,
+            filters,
+            ..Default::default()
+        })).await?;
+        let running = containers
+            .iter()
+            .filter(|container| container.state.as_deref() == Some("running"))
+            .count();
+
+        let state = match (running, containers.len()) {
+            (_, 0) => String::from("not created"),
+            (0, _) => String::from("stopped"),
+            (running, total) => format!("running ({}/{})", running, total),
+        };
+        rows.push(vec![
+            project.name.clone(),
+            state,
+            ago(project.last_used),
+            project.path.display().to_string(),
+        ]);
+    }
+
+    print_table(&["PROJECT", "STATE", "LAST USED", "PATH"], &rows);
+
+    Ok(())
+}
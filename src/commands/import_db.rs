@@ -0,0 +1,176 @@
+use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::utils::app_config::AppConfig;
+use crate::utils::compression::Compression;
+use crate::utils::database::{Database, Engine};
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::exit_code;
+use crate::utils::prompt::confirm;
+
+pub fn run(docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, project_root: &Path, file: Option<PathBuf>, drop: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let database = match Database::from_config(config, app_config) {
+        Ok(database) => database,
+        Err(error) => {
+            eprintln!("{}", error);
+            sysexits::ExitCode::Config.exit()
+        }
+    };
+
+    let dumps_dir = app_config.dumps_dir(project_root);
+    let file = match file {
+        // Dumps can also be given by their name in the dumps directory
+        Some(file) if !file.exists() && dumps_dir.join(&file).is_file() => dumps_dir.join(file),
+        Some(file) => file,
+        None => match newest_dump(&dumps_dir)? {
+            // Without a terminal there's nobody to confirm, and silently
+            // importing nothing would look like a success
+            Some(file) if !std::io::stdin().is_terminal() => {
+                eprintln!("Pass the dump to import, the newest one ({}) is only picked after confirming in a terminal", file.display());
+                sysexits::ExitCode::Usage.exit()
+            }
+            Some(file) if confirm(&format!("Import the newest dump {}?", file.display())) => file,
+            Some(_) => {
+                eprintln!("Skipped the import");
+                return Ok(());
+            }
+            None => {
+                eprintln!("There are no dumps in {}, pass the file to import", dumps_dir.display());
+                sysexits::ExitCode::NoInput.exit()
+            }
+        },
+    };
+    if !file.is_file() {
+        eprintln!("The file {} does not exist", file.display());
+        sysexits::ExitCode::NoInput.exit()
+    }
+
+    import(&docker_compose, &database, &file, drop)
+}
+
+/// Streams a (compressed) dump into the database, optionally dropping and
+/// creating the database first.
+pub fn import(docker_compose: &DockerCompose, database: &Database, file: &Path, drop: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if drop {
+        // Only PostgreSQL needs the version to know how to drop the database
+        let server_version = match database.engine {
+            Engine::Postgres => database.server_version(docker_compose),
+            _ => None,
+        };
+        let recreate = match database.recreate_command(server_version.as_deref()) {
+            Some(recreate) => recreate,
+            None => {
+                eprintln!("The database to recreate is unknown, set MYSQL_DATABASE for the '{}' service", database.service);
+                sysexits::ExitCode::Config.exit()
+            }
+        };
+        eprintln!("Recreating the database...");
+        let status = docker_compose
+            .exec_command(&database.service, None, None, &database.environment(), false)
+            .args(&recreate)
+            .join()?;
+        if !status.success() {
+            eprintln!("Recreating the database failed ({:?})", status);
+            std::process::exit(exit_code(status))
+        }
+    }
+
+    eprintln!("Importing {} into the {} database of '{}'...", file.display(), database.engine, database.service);
+    let total = file.metadata()?.len();
+    let reader = ProgressReader::new(File::open(file)?, total);
+    let mut reader = Compression::from_path(file).reader(reader)?;
+
+    let mut process = docker_compose
+        .exec_command(&database.service, None, None, &database.environment(), false)
+        .args(&database.import_command())
+        .stdin(subprocess::Redirection::Pipe)
+        .popen()?;
+
+    let mut input = process.stdin.take().unwrap();
+    let copied = std::io::copy(&mut reader, &mut input);
+    // Closing stdin lets the client know the dump is complete
+    std::mem::drop(input);
+    let status = process.wait()?;
+    eprintln!();
+
+    if let Err(error) = copied {
+        eprintln!("Could not read the dump ({})", error);
+        sysexits::ExitCode::IoErr.exit()
+    }
+    if !status.success() {
+        eprintln!("Importing the dump failed ({:?})", status);
+        std::process::exit(exit_code(status))
+    }
+
+    eprintln!("Imported {}", file.display());
+    Ok(())
+}
+
+/// Finds the most recently modified dump in the dumps directory.
+pub fn newest_dump(dumps_dir: &Path) -> std::io::Result<Option<PathBuf>> {
+    if !dumps_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let mut newest = None;
+    for entry in std::fs::read_dir(dumps_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.path().is_file() || ![".sql", ".sql.gz", ".sql.zst"].iter().any(|extension| name.ends_with(extension)) {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        if newest.as_ref().map(|(newest_modified, _)| modified > *newest_modified).unwrap_or(true) {
+            newest = Some((modified, entry.path()));
+        }
+    }
+
+    Ok(newest.map(|(_, path)| path))
+}
+
+/// Shows how much of the file has been read so far on stderr. As the file is
+/// read before decompressing, the progress is exact for compressed dumps too.
+struct ProgressReader<R: Read> {
+    inner: R,
+    read: u64,
+    total: u64,
+    last_shown: Option<Instant>,
+    visible: bool,
+}
+
+impl<R: Read> ProgressReader<R> {
+    fn new(inner: R, total: u64) -> Self {
+        Self {
+            inner,
+            read: 0,
+            total,
+            last_shown: None,
+            visible: std::io::stderr().is_terminal(),
+        }
+    }
+
+    fn show(&mut self) {
+        let percent = (self.read * 100).checked_div(self.total).unwrap_or(100);
+        eprint!(
+            "\r{:>3}% ({:.1} / {:.1} MB)",
+            percent,
+            self.read as f64 / 1_000_000.0,
+            self.total as f64 / 1_000_000.0
+        );
+        let _ = std::io::stderr().flush();
+        self.last_shown = Some(Instant::now());
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+        let due = self.last_shown.map(|last| last.elapsed() >= Duration::from_millis(100)).unwrap_or(true);
+        if self.visible && (due || read == 0) {
+            self.show();
+        }
+        Ok(read)
+    }
+}
@@ -0,0 +1,62 @@
+use std::io::IsTerminal;
+use std::path::Path;
+use crate::utils::prompt::select;
+use crate::utils::templates;
+
+pub fn run(templates_dir: &Path, template: Option<String>, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let templates = templates::all(templates_dir)?;
+
+    let template = match template {
+        Some(name) => match templates.iter().find(|template| template.name == name) {
+            Some(template) => template,
+            None => {
+                eprintln!("The template '{}' does not exist, available templates: {}", name, names(&templates));
+                sysexits::ExitCode::Usage.exit()
+            }
+        },
+        None if std::io::stdin().is_terminal() => {
+            let options: Vec<(&str, &str)> = templates
+                .iter()
+                .map(|template| (template.name.as_str(), template.description.as_str()))
+                .collect();
+            match select("Which template do you want to use?", &options) {
+                Some(index) => &templates[index],
+                None => {
+                    eprintln!("No valid template chosen");
+                    sysexits::ExitCode::Usage.exit()
+                }
+            }
+        }
+        None => {
+            eprintln!("Choose a template with --template, available templates: {}", names(&templates));
+            sysexits::ExitCode::Usage.exit()
+        }
+    };
+
+    let cwd = std::env::current_dir()?;
+    let existing: Vec<String> = template.files
+        .iter()
+        .filter(|(path, _)| cwd.join(path).exists())
+        .map(|(path, _)| path.display().to_string())
+        .collect();
+    if !existing.is_empty() && !force {
+        eprintln!("These files already exist, use --force to overwrite them: {}", existing.join(", "));
+        sysexits::ExitCode::CantCreat.exit()
+    }
+
+    for (path, content) in &template.files {
+        let target = cwd.join(path);
+        if let Some(directory) = target.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(&target, content)?;
+        println!("Created {}", path.display());
+    }
+    println!("Project initialized from the template '{}'", template.name);
+
+    Ok(())
+}
+
+fn names(templates: &[templates::Template]) -> String {
+    templates.iter().map(|template| template.name.as_str()).collect::<Vec<_>>().join(", ")
+}
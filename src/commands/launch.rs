@@ -0,0 +1,61 @@
+use crate::utils::app_config::AppConfig;
+use crate::utils::docker_compose::Config;
+use crate::utils::traefik;
+
+pub fn run(config: &Config, app_config: &AppConfig, print: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let url = match url(config, app_config) {
+        Some(url) => url,
+        None => {
+            eprintln!("Could not determine the URL of the project, set 'launch_url' in your config file");
+            sysexits::ExitCode::Unavailable.exit()
+        }
+    };
+
+    if print {
+        println!("{}", url);
+        return Ok(());
+    }
+
+    println!("Opening {}", url);
+    let cmd = if cfg!(target_os = "macos") {
+        subprocess::Exec::cmd("open").arg(&url)
+    } else if cfg!(target_os = "windows") {
+        subprocess::Exec::cmd("cmd").arg("/C").arg("start").arg("").arg(&url)
+    } else {
+        subprocess::Exec::cmd("xdg-open").arg(&url)
+    };
+    if !cmd.join()?.success() {
+        eprintln!("Could not open the browser, open {} manually", url);
+        sysexits::ExitCode::Unavailable.exit()
+    }
+
+    Ok(())
+}
+
+/// Determines the URL of the project, in order of preference from the config
+/// file, the host names routed by Traefik (preferring HTTPS) or the first
+/// published port.
+fn url(config: &Config, app_config: &AppConfig) -> Option<String> {
+    if let Some(url) = &app_config.launch_url {
+        return Some(traefik::resolve(url, &config.name));
+    }
+
+    let mut routes: Vec<traefik::Route> = config.services
+        .values()
+        .filter_map(|service| service.labels.as_ref())
+        .flat_map(|labels| traefik::routes(labels, &config.name))
+        .collect();
+    routes.sort_by_key(|route| !route.tls);
+    if let Some(route) = routes.first() {
+        return Some(route.url());
+    }
+
+    config.services
+        .values()
+        .flat_map(|service| service.ports.iter().flatten())
+        .find(|port| !port.published.is_empty())
+        .map(|port| {
+            let scheme = if port.target == 443 { "https" } else { "http" };
+            format!("{}://localhost:{}/", scheme, port.published)
+        })
+}
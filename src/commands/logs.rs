@@ -0,0 +1,216 @@
+use bollard::Docker;
+use bollard::container::LogsOptions;
+use futures_util::StreamExt;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use crate::utils::app_config::AppConfig;
+use crate::utils::container::project_containers;
+use crate::utils::docker_compose::Config;
+use crate::utils::time::now;
+
+/// ANSI colours for the prefixes, assigned to the services in turn
+const COLOURS: [&str; 6] = ["36", "33", "32", "35", "34", "31"];
+
+/// Label docker compose puts on containers with their number within the service
+const LABEL_CONTAINER_NUMBER: &str = "com.docker.compose.container-number";
+
+pub struct LogsArgs {
+    pub services: Vec<String>,
+    pub follow: bool,
+    pub since: Option<String>,
+    pub tail: Option<String>,
+    pub grep: Option<String>,
+    pub timestamps: bool,
+}
+
+pub async fn run(docker: &Docker, config: &Config, app_config: &AppConfig, args: LogsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let services: Vec<String> = if args.services.is_empty() {
+        config.services.keys().cloned().collect()
+    } else {
+        match args.services.iter().map(|service| config.resolve_service(app_config, Some(service))).collect() {
+            Ok(services) => services,
+            Err(error) => {
+                eprintln!("{}", error);
+                sysexits::ExitCode::Usage.exit()
+            }
+        }
+    };
+
+    let since = match args.since.as_deref().map(parse_since) {
+        Some(Some(since)) => since,
+        Some(None) => {
+            eprintln!("Invalid value for --since, use a duration like 30s, 10m, 2h or 1d, or a UNIX timestamp");
+            sysexits::ExitCode::Usage.exit()
+        }
+        None => 0,
+    };
+    let grep = match args.grep.as_deref().map(Regex::new) {
+        Some(Ok(grep)) => Some(grep),
+        Some(Err(error)) => {
+            eprintln!("Invalid regular expression for --grep: {}", error);
+            sysexits::ExitCode::Usage.exit()
+        }
+        None => None,
+    };
+    let colour = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let width = services.iter().map(String::len).max().unwrap_or_default() + 2;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<(String, String)>();
+    let mut tasks = Vec::new();
+    for (index, service) in services.into_iter().enumerate() {
+        let follower = ServiceFollower {
+            docker: docker.clone(),
+            project: config.name.clone(),
+            service,
+            colour: colour.then_some(COLOURS[index % COLOURS.len()]),
+            width,
+            follow: args.follow,
+            since,
+            tail: args.tail.clone().unwrap_or(String::from("all")),
+            timestamps: args.timestamps,
+        };
+        tasks.push(tokio::spawn(follower.run(sender.clone())));
+    }
+
+    // Only the followers hold a sender now, so receiving ends once all are done
+    drop(sender);
+    while let Some((prefix, line)) = receiver.recv().await {
+        if grep.as_ref().map(|grep| grep.is_match(&line)).unwrap_or(true) {
+            println!("{}{}", prefix, line);
+        }
+    }
+    for task in tasks {
+        task.await??;
+    }
+
+    Ok(())
+}
+
+/// Streams the logs of all containers of a service. When following, it keeps
+/// looking for containers that (re)started, so restarted and recreated
+/// containers are picked up again.
+struct ServiceFollower {
+    docker: Docker,
+    project: String,
+    service: String,
+    colour: Option<&'static str>,
+    width: usize,
+    follow: bool,
+    since: i64,
+    tail: String,
+    timestamps: bool,
+}
+
+impl ServiceFollower {
+    async fn run(self, sender: mpsc::UnboundedSender<(String, String)>) -> Result<(), bollard::errors::Error> {
+        // The containers streamed so far: `None` while attached, otherwise when
+        // their stream ended. Both are kept under one lock, so a container is
+        // never seen as neither attached nor ended.
+        let streamed: Arc<Mutex<HashMap<String, Option<i64>>>> = Arc::default();
+        let mut streams = Vec::new();
+
+        loop {
+            let containers = project_containers(&self.docker, &self.project, Some(&self.service)).await?;
+            for container in containers {
+                let id = container.id.unwrap_or_default();
+                let running = container.state.as_deref() == Some("running");
+                let ended = match streamed.lock().unwrap().get(&id) {
+                    Some(None) => continue,
+                    // Stopped containers only have to be shown once
+                    Some(Some(_)) if !running => continue,
+                    Some(ended) => *ended,
+                    None => None,
+                };
+
+                let number = container.labels
+                    .and_then(|labels| labels.get(LABEL_CONTAINER_NUMBER).cloned())
+                    .filter(|number| number != "1");
+                let name = match number {
+                    Some(number) => format!("{}-{}", self.service, number),
+                    None => self.service.clone(),
+                };
+                let prefix = match self.colour {
+                    Some(colour) => format!("\x1b[{}m{:<width$}|\x1b[0m ", colour, name, width = self.width),
+                    None => format!("{:<width$}| ", name, width = self.width),
+                };
+
+                // Continue where the previous stream of a restarted container stopped
+                let options = LogsOptions::<String> {
+                    follow: self.follow,
+                    stdout: true,
+                    stderr: true,
+                    since: ended.unwrap_or(self.since),
+                    timestamps: self.timestamps,
+                    tail: if ended.is_some() { String::from("all") } else { self.tail.clone() },
+                    ..Default::default()
+                };
+
+                streamed.lock().unwrap().insert(id.clone(), None);
+                let docker = self.docker.clone();
+                let sender = sender.clone();
+                let streamed = streamed.clone();
+                streams.push(tokio::spawn(async move {
+                    let mut stream = docker.logs(&id, Some(options));
+                    let mut buffer = String::new();
+                    while let Some(Ok(output)) = stream.next().await {
+                        buffer.push_str(&output.to_string());
+                        while let Some(end) = buffer.find('\n') {
+                            let line: String = buffer.drain(..=end).collect();
+                            let _ = sender.send((prefix.clone(), line.trim_end_matches(['\r', '\n']).to_string()));
+                        }
+                    }
+                    if !buffer.is_empty() {
+                        let _ = sender.send((prefix, buffer));
+                    }
+                    streamed.lock().unwrap().insert(id, Some(now() as i64));
+                }));
+            }
+
+            if !self.follow {
+                for stream in streams {
+                    let _ = stream.await;
+                }
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+}
+
+/// Parses a relative time like "10m" into a UNIX timestamp that long ago, or
+/// takes a UNIX timestamp as is.
+fn parse_since(since: &str) -> Option<i64> {
+    if let Ok(timestamp) = since.parse::<i64>() {
+        return Some(timestamp);
+    }
+
+    let unit = since.chars().last()?;
+    let amount: i64 = since[..since.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => amount,
+        'm' => amount * 60,
+        'h' => amount * 3600,
+        'd' => amount * 86400,
+        _ => return None,
+    };
+    Some(now() as i64 - seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_since_durations_and_timestamps() {
+        let now = now() as i64;
+        assert_eq!(parse_since("1700000000"), Some(1700000000));
+        assert!((parse_since("10m").unwrap() - (now - 600)).abs() <= 1);
+        assert!((parse_since("2h").unwrap() - (now - 7200)).abs() <= 1);
+        assert_eq!(parse_since("10 minutes"), None);
+        assert_eq!(parse_since(""), None);
+    }
+}
@@ -1 +1,16 @@
+pub mod config;
 pub mod exec;
+pub mod export_db;
+pub mod global_status;
+pub mod import_db;
+pub mod init;
+pub mod launch;
+pub mod logs;
+pub mod poweroff;
+pub mod restart;
+pub mod run;
+pub mod shell;
+pub mod snapshot;
+pub mod status;
+pub mod start;
+pub mod stop;
@@ -0,0 +1,126 @@
+use bollard::Docker;
+use bollard::container::ListContainersOptions;
+use bollard::service::ContainerSummary;
+use std::collections::{BTreeMap, HashMap};
+use crate::utils::container::LABEL_PROJECT;
+use crate::utils::docker_compose::DockerCompose;
+use crate::utils::general::SHARED_NETWORK;
+
+/// Compose project name of the containers dev-cli runs itself (Traefik, etc.)
+const INFRASTRUCTURE_PROJECT: &str = "dev-cli";
+
+pub async fn run(docker: &Docker, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut filters = HashMap::new();
+    filters.insert("network", vec![SHARED_NETWORK]);
+    let containers = docker.list_containers(Some(ListContainersOptions {
+        filters,
+        ..Default::default()
+    })).await?;
+
+    let Running { projects, infrastructure } = group_by_project(containers);
+
+    if projects.is_empty() && infrastructure.is_empty() {
+        println!("Nothing is running");
+        return Ok(());
+    }
+
+    if dry_run {
+        for (project, containers) in &projects {
+            println!("Would stop project '{}' ({})", project, containers.join(", "));
+        }
+        for (_, name) in &infrastructure {
+            println!("Would stop shared container '{}'", name);
+        }
+        return Ok(());
+    }
+
+    for project in projects.keys() {
+        println!("Stopping project '{}'...", project);
+        DockerCompose::stop_project(project)?;
+    }
+    for (id, name) in &infrastructure {
+        println!("Stopping shared container '{}'...", name);
+        docker.stop_container(id, None).await?;
+    }
+
+    if !projects.is_empty() {
+        println!("Stopped projects: {}", projects.keys().cloned().collect::<Vec<_>>().join(", "));
+    }
+    if !infrastructure.is_empty() {
+        println!("Stopped shared containers: {}", infrastructure.into_iter().map(|(_, name)| name).collect::<Vec<_>>().join(", "));
+    }
+
+    Ok(())
+}
+
+/// The containers on the shared network
+#[derive(Debug, Default)]
+struct Running {
+    /// Container names by compose project
+    projects: BTreeMap<String, Vec<String>>,
+    /// Containers that are not part of a project and shared between all of
+    /// them, as id and name
+    infrastructure: Vec<(String, String)>,
+}
+
+/// Groups the names of the containers by their compose project.
+fn group_by_project(containers: Vec<ContainerSummary>) -> Running {
+    let mut running = Running::default();
+    for container in containers {
+        let id = container.id.unwrap_or_default();
+        let name = container.names
+            .and_then(|names| names.into_iter().next())
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or(id.clone());
+        match container.labels.and_then(|mut labels| labels.remove(LABEL_PROJECT)) {
+            Some(project) if project != INFRASTRUCTURE_PROJECT => {
+                running.projects.entry(project).or_default().push(name)
+            }
+            _ => running.infrastructure.push((id, name)),
+        }
+    }
+    running
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn container(id: &str, name: &str, project: Option<&str>) -> ContainerSummary {
+        ContainerSummary {
+            id: Some(String::from(id)),
+            names: Some(vec![format!("/{}", name)]),
+            labels: project.map(|project| HashMap::from([(String::from(LABEL_PROJECT), String::from(project))])),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn group_containers_by_project() {
+        let Running { projects, infrastructure } = group_by_project(vec![
+            container("1", "shop-php-1", Some("shop")),
+            container("2", "traefik", Some(INFRASTRUCTURE_PROJECT)),
+            container("3", "blog-nginx-1", Some("blog")),
+            container("4", "shop-db-1", Some("shop")),
+            container("5", "mailpit", None),
+        ]);
+        assert_eq!(projects, BTreeMap::from([
+            (String::from("blog"), vec![String::from("blog-nginx-1")]),
+            (String::from("shop"), vec![String::from("shop-php-1"), String::from("shop-db-1")]),
+        ]));
+        assert_eq!(infrastructure, vec![
+            (String::from("2"), String::from("traefik")),
+            (String::from("5"), String::from("mailpit")),
+        ]);
+    }
+
+    #[test]
+    fn examples_join_the_shared_network() -> Result<(), Box<dyn std::error::Error>> {
+        for example in std::fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("examples"))? {
+            let compose: serde_yaml::Value = serde_yaml::from_reader(std::fs::File::open(example?.path().join("compose.yml"))?)?;
+            assert_eq!(compose["networks"][SHARED_NETWORK]["external"], serde_yaml::Value::Bool(true));
+        }
+        Ok(())
+    }
+}
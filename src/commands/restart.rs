@@ -0,0 +1,31 @@
+use bollard::Docker;
+use std::time::Duration;
+use crate::utils::app_config::AppConfig;
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::readiness::wait_until_ready;
+
+pub async fn run(docker: &Docker, docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, services: Vec<String>, timeout: u64) -> Result<(), Box<dyn std::error::Error>> {
+    if services.is_empty() {
+        docker_compose.down()?;
+        docker_compose.up(&[])?;
+    } else {
+        let services: Vec<String> = match services.iter().map(|service| config.resolve_service(app_config, Some(service))).collect() {
+            Ok(services) => services,
+            Err(error) => {
+                eprintln!("{}", error);
+                sysexits::ExitCode::Usage.exit()
+            }
+        };
+
+        let affected = config.with_dependents(&services);
+        println!("Recreating {}", affected.join(", "));
+        docker_compose.remove(&affected)?;
+        docker_compose.up(&affected)?;
+    }
+
+    println!("Waiting for the services to become ready...");
+    wait_until_ready(docker, config, &config.startup_order(), Duration::from_secs(timeout)).await?;
+    println!("Project '{}' is up and running", config.name);
+
+    Ok(())
+}
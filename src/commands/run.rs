@@ -0,0 +1,205 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+use subprocess::ExitStatus;
+use crate::utils::app_config::{AppConfig, RunCommand, RunCommandStep};
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::exit_code;
+
+pub fn run(docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, name: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let run_commands = app_config.run_commands.as_ref();
+    let name = match name {
+        Some(name) => name,
+        None => {
+            list(app_config);
+            return Ok(());
+        }
+    };
+    let run_command = match run_commands.and_then(|commands| commands.get(&name)) {
+        Some(run_command) => run_command,
+        None => {
+            eprintln!("The command '{}' is not defined in the config file", name);
+            list(app_config);
+            sysexits::ExitCode::Usage.exit()
+        }
+    };
+
+    // Every step starts where we are on the host, if that directory is mounted
+    let containers: BTreeSet<&str> = run_command.commands.iter().map(|step| step.container.as_str()).collect();
+    let workdirs: BTreeMap<&str, Option<String>> = containers
+        .into_iter()
+        .map(|container| (container, config.workdir(container, None)))
+        .collect();
+
+    let failed_code = if run_command.parallel {
+        run_parallel(&docker_compose, run_command, &workdirs)?
+    } else {
+        run_sequential(&docker_compose, run_command, &workdirs)?
+    };
+    if let Some(code) = failed_code {
+        std::process::exit(code)
+    }
+
+    Ok(())
+}
+
+fn list(app_config: &AppConfig) {
+    let run_commands = match &app_config.run_commands {
+        Some(run_commands) if !run_commands.is_empty() => run_commands,
+        _ => {
+            println!("No commands defined, add them under 'run-commands' in your config file");
+            return;
+        }
+    };
+
+    let width = run_commands.keys().map(String::len).max().unwrap_or_default();
+    println!("Available commands:");
+    for (name, run_command) in run_commands {
+        println!("  {:<width$}  {}", name, run_command.description.as_deref().unwrap_or_default(), width = width);
+    }
+}
+
+/// Runs one step after another with a TTY, stopping at the first failing one.
+/// Returns the exit code of the failed step.
+fn run_sequential(docker_compose: &DockerCompose, run_command: &RunCommand, workdirs: &BTreeMap<&str, Option<String>>) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+    let failed_code = until_failed(&run_command.commands, |step| {
+        docker_compose
+            .exec_shell_command(&step.container, step.user.as_deref(), workdirs[step.container.as_str()].as_deref(), &step.command, true)
+            .join()
+    })?;
+    Ok(failed_code)
+}
+
+/// Runs the steps with `run_step` until one of them fails, returning its exit code.
+fn until_failed(steps: &[RunCommandStep], mut run_step: impl FnMut(&RunCommandStep) -> subprocess::Result<ExitStatus>) -> subprocess::Result<Option<i32>> {
+    for step in steps {
+        let status = run_step(step)?;
+        if !status.success() {
+            eprintln!("'{}' failed in '{}' ({:?})", step.command, step.container, status);
+            return Ok(Some(exit_code(status)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Runs all steps at the same time, printing their output line by line as it
+/// arrives, prefixed with the container it comes from. Returns the exit code
+/// of the first failed step.
+fn run_parallel(docker_compose: &DockerCompose, run_command: &RunCommand, workdirs: &BTreeMap<&str, Option<String>>) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+    let prefixes = prefixes(&run_command.commands);
+    let width = prefixes.iter().map(String::len).max().unwrap_or_default();
+
+    let (sender, receiver) = mpsc::channel::<String>();
+    let mut processes = Vec::new();
+    let mut readers = Vec::new();
+    for (step, prefix) in run_command.commands.iter().zip(&prefixes) {
+        let mut process = docker_compose
+            .exec_shell_command(&step.container, step.user.as_deref(), workdirs[step.container.as_str()].as_deref(), &step.command, false)
+            .stdout(subprocess::Redirection::Pipe)
+            .stderr(subprocess::Redirection::Merge)
+            .popen()?;
+
+        let output = process.stdout.take().unwrap();
+        let sender = sender.clone();
+        let prefix = format!("{:<width$} |", prefix, width = width);
+        readers.push(std::thread::spawn(move || {
+            for line in BufReader::new(output).lines().map_while(Result::ok) {
+                sender.send(format!("{} {}", prefix, line)).unwrap();
+            }
+        }));
+        processes.push((step, process));
+    }
+
+    // Only the readers hold a sender now, so receiving ends once all are done
+    drop(sender);
+    for line in receiver {
+        println!("{}", line);
+    }
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    let mut statuses = Vec::new();
+    for (step, mut process) in processes {
+        statuses.push((step, process.wait()?));
+    }
+
+    Ok(first_failed(&statuses))
+}
+
+/// The prefix of the output of every step, which is its container. Steps
+/// sharing a container are told apart by their position, e.g. `php#2`.
+fn prefixes(steps: &[RunCommandStep]) -> Vec<String> {
+    steps.iter().enumerate().map(|(index, step)| {
+        let same_container = steps.iter().filter(|other| other.container == step.container).count();
+        if same_container > 1 {
+            format!("{}#{}", step.container, index + 1)
+        } else {
+            step.container.clone()
+        }
+    }).collect()
+}
+
+/// Reports every failed step, returning the exit code of the first one.
+fn first_failed(statuses: &[(&RunCommandStep, ExitStatus)]) -> Option<i32> {
+    let mut failed_code = None;
+    for (step, status) in statuses {
+        if !status.success() {
+            eprintln!("'{}' failed in '{}' ({:?})", step.command, step.container, status);
+            failed_code.get_or_insert(exit_code(*status));
+        }
+    }
+    failed_code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(container: &str, command: &str) -> RunCommandStep {
+        RunCommandStep {
+            container: String::from(container),
+            user: None,
+            command: String::from(command),
+        }
+    }
+
+    #[test]
+    fn prefixes_of_repeated_containers() {
+        let steps = [step("php", "composer test"), step("node", "npm test"), step("php", "phpstan")];
+        assert_eq!(prefixes(&steps), vec!["php#1", "node", "php#3"]);
+        assert_eq!(prefixes(&[step("php", "composer test")]), vec!["php"]);
+    }
+
+    #[test]
+    fn sequential_steps_stop_at_the_first_failure() {
+        let steps = [step("php", "true"), step("php", "exit 3"), step("node", "true")];
+        let mut executed = Vec::new();
+        let failed_code = until_failed(&steps, |step| {
+            executed.push(step.command.clone());
+            Ok(match step.command.as_str() {
+                "exit 3" => ExitStatus::Exited(3),
+                _ => ExitStatus::Exited(0),
+            })
+        }).unwrap();
+        assert_eq!(failed_code, Some(3));
+        assert_eq!(executed, vec!["true", "exit 3"]);
+
+        let failed_code = until_failed(&steps[..1], |_| Ok(ExitStatus::Exited(0))).unwrap();
+        assert_eq!(failed_code, None);
+    }
+
+    #[test]
+    fn parallel_steps_exit_with_the_first_failure() {
+        let steps = [step("php", "true"), step("node", "exit 2"), step("db", "kill -9 $$")];
+        let statuses = [
+            (&steps[0], ExitStatus::Exited(0)),
+            (&steps[1], ExitStatus::Exited(2)),
+            (&steps[2], ExitStatus::Signaled(9)),
+        ];
+        assert_eq!(first_failed(&statuses), Some(2));
+        assert_eq!(first_failed(&statuses[2..]), Some(137));
+        assert_eq!(first_failed(&statuses[..1]), None);
+    }
+}
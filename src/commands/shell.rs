@@ -0,0 +1,45 @@
+use crate::utils::app_config::AppConfig;
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::exit_code;
+
+/// Shells tried in this order when none is configured for a service
+const SHELLS: [&str; 4] = ["bash", "zsh", "ash", "sh"];
+
+pub fn run(docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, service: Option<String>, user: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let service = match config.resolve_service(app_config, service.as_deref()) {
+        Ok(service) => service,
+        Err(error) => {
+            eprintln!("{}", error);
+            sysexits::ExitCode::Usage.exit()
+        }
+    };
+    let service_config = app_config.service(&service);
+
+    let shell = match service_config.and_then(|service_config| service_config.shell.clone()) {
+        Some(shell) => shell,
+        None => match SHELLS.iter().find(|shell| docker_compose.has_program(&service, shell)) {
+            Some(shell) => shell.to_string(),
+            None => {
+                eprintln!("Could not find a shell in the container of '{}' (tried {})", service, SHELLS.join(", "));
+                sysexits::ExitCode::Unavailable.exit()
+            }
+        },
+    };
+    let user = user.or_else(|| service_config.and_then(|service_config| service_config.user.clone()));
+
+    // Start where we are on the host if that directory is mounted, else in the
+    // configured directory
+    let workdir = config.workdir(&service, service_config.and_then(|service_config| service_config.workdir.as_deref()));
+    let env = app_config.service_env(&service, Vec::new());
+
+    let status = docker_compose
+        .exec_command(&service, user.as_deref(), workdir.as_deref(), &env, true)
+        .arg(shell)
+        .arg("-l")
+        .join()?;
+    if !status.success() {
+        std::process::exit(exit_code(status))
+    }
+
+    Ok(())
+}
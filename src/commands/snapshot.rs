@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::path::Path;
+use crate::commands::export_db::export;
+use crate::commands::import_db::import;
+use crate::utils::app_config::AppConfig;
+use crate::utils::compression::Compression;
+use crate::utils::database::Database;
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::SnapshotCommands;
+use crate::utils::snapshot::Snapshot;
+use crate::utils::table::print_table;
+use crate::utils::time::{ago, now};
+
+pub fn run(docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, project_root: &Path, command: SnapshotCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Snapshot::directory(&app_config.dumps_dir(project_root));
+
+    match command {
+        SnapshotCommands::Create { name } => {
+            let database = database(config, app_config);
+            let name = name.unwrap_or(chrono::Local::now().format("%Y%m%d-%H%M%S").to_string());
+            if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+                eprintln!("The snapshot name '{}' is not a valid file name", name);
+                sysexits::ExitCode::Usage.exit()
+            }
+            if Snapshot::load(&directory, &name)?.is_some() {
+                eprintln!("The snapshot '{}' already exists", name);
+                sysexits::ExitCode::CantCreat.exit()
+            }
+
+            std::fs::create_dir_all(&directory)?;
+            let dump_file = Snapshot::dump_file(&directory, &name);
+            eprintln!("Creating the snapshot '{}' of the {} database...", name, database.engine);
+            export(&docker_compose, &database, Compression::Gzip.writer(File::create(&dump_file)?)?, &dump_file)?;
+
+            let snapshot = Snapshot {
+                name,
+                engine: database.engine,
+                image: database.image.clone(),
+                version: database.server_version(&docker_compose),
+                created_at: now(),
+                git_commit: git_commit(project_root),
+            };
+            snapshot.save(&directory)?;
+            println!("Created the snapshot '{}'", snapshot.name);
+        }
+        SnapshotCommands::List => {
+            let snapshots = Snapshot::load_all(&directory)?;
+            if snapshots.is_empty() {
+                println!("There are no snapshots yet, create one with `dev-cli snapshot create`");
+                return Ok(());
+            }
+
+            let rows: Vec<Vec<String>> = snapshots.iter().map(|snapshot| {
+                let size = Snapshot::dump_file(&directory, &snapshot.name)
+                    .metadata()
+                    .map(|metadata| format!("{:.1} MB", metadata.len() as f64 / 1_000_000.0))
+                    .unwrap_or(String::from("missing"));
+                vec![
+                    snapshot.name.clone(),
+                    format!("{} {}", snapshot.engine, snapshot.version.as_deref().unwrap_or("")).trim_end().to_string(),
+                    ago(snapshot.created_at),
+                    snapshot.git_commit.as_deref().map(|commit| commit.chars().take(8).collect()).unwrap_or(String::from("-")),
+                    size,
+                ]
+            }).collect();
+            print_table(&["NAME", "ENGINE", "CREATED", "COMMIT", "SIZE"], &rows);
+        }
+        SnapshotCommands::Restore { name } => {
+            let database = database(config, app_config);
+            let snapshot = match Snapshot::load(&directory, &name)? {
+                Some(snapshot) => snapshot,
+                None => {
+                    eprintln!("The snapshot '{}' does not exist", name);
+                    sysexits::ExitCode::NoInput.exit()
+                }
+            };
+
+            match snapshot.check_compatible(database.engine, database.server_version(&docker_compose).as_deref()) {
+                Ok(true) => {}
+                Ok(false) => eprintln!("Could not determine the database versions, restoring without checking them"),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    sysexits::ExitCode::DataErr.exit()
+                }
+            }
+
+            // There's no single database to recreate for a dump of all
+            // databases, it's imported over the existing ones instead
+            let recreate = database.name().is_some();
+            if !recreate {
+                eprintln!("The snapshot contains all databases, importing it without recreating them");
+            }
+            import(&docker_compose, &database, &Snapshot::dump_file(&directory, &name), recreate)?;
+            println!("Restored the snapshot '{}'", name);
+        }
+        SnapshotCommands::Prune { keep } => {
+            let snapshots = Snapshot::load_all(&directory)?;
+            let outdated = Snapshot::outdated(snapshots, keep);
+            for snapshot in &outdated {
+                snapshot.remove(&directory)?;
+                println!("Removed the snapshot '{}' ({})", snapshot.name, ago(snapshot.created_at));
+            }
+            if outdated.is_empty() {
+                println!("Nothing to remove");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn database(config: &Config, app_config: &AppConfig) -> Database {
+    match Database::from_config(config, app_config) {
+        Ok(database) => database,
+        Err(error) => {
+            eprintln!("{}", error);
+            sysexits::ExitCode::Config.exit()
+        }
+    }
+}
+
+fn git_commit(project_root: &Path) -> Option<String> {
+    subprocess::Exec::cmd("git")
+        .arg("rev-parse")
+        .arg("HEAD")
+        .cwd(project_root)
+        .stderr(subprocess::NullFile)
+        .capture()
+        .ok()
+        .filter(|capture| capture.success())
+        .map(|capture| capture.stdout_str().trim().to_string())
+}
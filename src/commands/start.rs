@@ -0,0 +1,14 @@
+use bollard::Docker;
+use std::time::Duration;
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::readiness::wait_until_ready;
+
+pub async fn run(docker: &Docker, docker_compose: DockerCompose, config: &Config, timeout: u64) -> Result<(), Box<dyn std::error::Error>> {
+    docker_compose.up(&[])?;
+
+    println!("Waiting for the services to become ready...");
+    wait_until_ready(docker, config, &config.startup_order(), Duration::from_secs(timeout)).await?;
+    println!("Project '{}' is up and running", config.name);
+
+    Ok(())
+}
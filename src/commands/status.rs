@@ -0,0 +1,85 @@
+use bollard::Docker;
+use bollard::models::{ContainerSummary, Port};
+use std::collections::BTreeSet;
+use crate::utils::container::project_containers;
+use crate::utils::docker_compose::Config;
+use crate::utils::table::print_table;
+use crate::utils::traefik;
+
+pub async fn run(docker: &Docker, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rows = Vec::new();
+    for (name, service) in &config.services {
+        let hosts: BTreeSet<String> = service.labels
+            .iter()
+            .flat_map(|labels| traefik::routes(labels, &config.name))
+            .map(|route| route.host)
+            .collect();
+        let hosts = join_or_dash(hosts);
+
+        let containers = project_containers(docker, &config.name, Some(name)).await?;
+        if containers.is_empty() {
+            rows.push(vec![
+                name.clone(),
+                String::from("-"),
+                String::from("not created"),
+                String::from("-"),
+                String::from("-"),
+                String::from("-"),
+                hosts.clone(),
+            ]);
+            continue;
+        }
+
+        for container in containers {
+            let inspect = docker.inspect_container(container.id.as_deref().unwrap_or_default(), None).await?;
+            let state = inspect.state.unwrap_or_default();
+            let health = state.health
+                .and_then(|health| health.status)
+                .map(|status| status.to_string())
+                .filter(|status| !status.is_empty() && status != "none")
+                .unwrap_or(String::from("-"));
+
+            rows.push(vec![
+                name.clone(),
+                inspect.name.unwrap_or_default().trim_start_matches('/').to_string(),
+                state.status.map(|status| status.to_string()).unwrap_or(String::from("unknown")),
+                health,
+                uptime(&container),
+                join_or_dash(published_ports(container.ports.unwrap_or_default())),
+                hosts.clone(),
+            ]);
+        }
+    }
+
+    print_table(&["SERVICE", "CONTAINER", "STATE", "HEALTH", "UPTIME", "PORTS", "HOSTS"], &rows);
+
+    Ok(())
+}
+
+/// Takes the uptime from the status docker reports, e.g. "Up 5 minutes (healthy)".
+fn uptime(container: &ContainerSummary) -> String {
+    container.status
+        .as_deref()
+        .and_then(|status| status.strip_prefix("Up "))
+        .map(|uptime| uptime.split(" (").next().unwrap_or(uptime).to_string())
+        .unwrap_or(String::from("-"))
+}
+
+/// Formats the published ports, ports published on IPv4 and IPv6 are only listed once.
+fn published_ports(ports: Vec<Port>) -> BTreeSet<String> {
+    ports
+        .into_iter()
+        .filter_map(|port| {
+            let protocol = port.typ.map(|typ| typ.to_string()).unwrap_or(String::from("tcp"));
+            port.public_port.map(|public_port| format!("{}->{}/{}", public_port, port.private_port, protocol))
+        })
+        .collect()
+}
+
+fn join_or_dash(values: BTreeSet<String>) -> String {
+    if values.is_empty() {
+        String::from("-")
+    } else {
+        values.into_iter().collect::<Vec<_>>().join(", ")
+    }
+}
@@ -0,0 +1,37 @@
+use bollard::Docker;
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::prompt::confirm;
+
+pub async fn run(docker: &Docker, docker_compose: DockerCompose, config: &Config, remove_data: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Only volumes that actually exist can be removed, list exactly those
+    let mut volumes = Vec::new();
+    if remove_data {
+        for volume in config.project_volumes() {
+            if docker.inspect_volume(&volume.name).await.is_ok() {
+                volumes.push(volume.name.as_str());
+            }
+        }
+
+        if volumes.is_empty() {
+            println!("The project has no volumes to remove");
+        } else {
+            println!("The following volumes will be removed, including all data in them:");
+            for volume in &volumes {
+                println!("  - {}", volume);
+            }
+            if !yes && !confirm("Do you want to continue?") {
+                println!("Aborted, nothing was stopped or removed");
+                return Ok(());
+            }
+        }
+    }
+
+    docker_compose.down()?;
+
+    for volume in volumes {
+        docker.remove_volume(volume, None).await?;
+        println!("Removed volume '{}'", volume);
+    }
+
+    Ok(())
+}
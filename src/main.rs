@@ -5,8 +5,8 @@ mod commands;
 mod utils;
 
 use bollard::Docker;
-use clap::Parser;
-use std::path::{PathBuf, Path};
+use clap::{CommandFactory, Parser};
+use std::path::PathBuf;
 use crate::utils::path::find_recursively; // Used for writing assertions
 use crate::utils::general::{Cli, Commands, is_docker_required, docker_running, check_and_setup_system, check_and_setup_docker};
 
@@ -16,7 +16,7 @@ use assert_cmd::prelude::*; // Add methods on commands
 use predicates::prelude::*;
 
 // Parameters for config
-// - docker-compose-path: Path to the docker-compose file (default: {project-root}/compose.yml)
+// - compose_files: Paths to the docker compose files (default: {project-root}/compose.yaml or one of the other default names)
 // - build-script-path: Path to the build script (ex.: ./docker/build/build.sh)
 // - run-commands: List of commands to run in the container (ex.: [
 //    "dev": {commands: [{container: "node", user: "node", command: "yarn run dev"}]},
@@ -43,6 +43,8 @@ lazy_static! {
         .iter()
         .collect()
     };
+    static ref REGISTRY_FILE_PATH: PathBuf = CONFIG_FILE_PATH_GLOBAL.with_file_name("projects.yml");
+    static ref TEMPLATES_DIR_PATH: PathBuf = CONFIG_FILE_PATH_GLOBAL.with_file_name("templates");
 }
 
 #[tokio::main]
@@ -62,19 +64,46 @@ async fn main() -> Result<sysexits::ExitCode, Box<dyn std::error::Error>> {
         check_and_setup_docker(&docker).await;
     }
 
-    println! {"Global config at {}", CONFIG_FILE_PATH_GLOBAL.clone().into_os_string().into_string().unwrap()};
-
     // Find .dev-cli.yml/.dev-cli.dist.yml in the current directory or any
     // parent directory to determine the project root
     let cwd = std::env::current_dir()?;
-
-    let project_root = match  (
+    let project_root = match (
         find_recursively(&cwd, CONFIG_FILE_NAME_LOCAL),
         find_recursively(&cwd, CONFIG_FILE_NAME_PROJECT)
     ) {
-        (Some(filepath), _) => filepath,
-        (_, Some(filepath)) => filepath,
-        (None, None) => {
+        (Some(filepath), _) | (_, Some(filepath)) => Some(filepath.parent().unwrap().to_path_buf()),
+        (None, None) => None,
+    };
+    let config_files = utils::app_config::ConfigFiles {
+        global: CONFIG_FILE_PATH_GLOBAL.clone(),
+        project: project_root.as_ref().map(|project_root| project_root.join(CONFIG_FILE_NAME_PROJECT)),
+        local: project_root.as_ref().map(|project_root| project_root.join(CONFIG_FILE_NAME_LOCAL)),
+    };
+
+    // Commands that don't need a project, or have to work with a broken config
+    match cli.command {
+        Some(Commands::Config { command }) => {
+            commands::config::run(&config_files, project_root.as_deref(), command)?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(Commands::Init { template, force }) => {
+            commands::init::run(&TEMPLATES_DIR_PATH, template, force)?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(Commands::Poweroff { dry_run }) => {
+            commands::poweroff::run(&docker, dry_run).await?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(Commands::GlobalStatus) => {
+            commands::global_status::run(&docker, &REGISTRY_FILE_PATH).await?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        _ => {}
+    }
+
+    let project_root = match project_root {
+        Some(project_root) => project_root,
+        None => {
             eprintln!( "Could not find a project root. Please add a {} or {} to your project root",
                 CONFIG_FILE_NAME_LOCAL, CONFIG_FILE_NAME_PROJECT
             );
@@ -82,26 +111,33 @@ async fn main() -> Result<sysexits::ExitCode, Box<dyn std::error::Error>> {
         }
     };
 
-    println!("project root: {:?}", &project_root);
-    let app_config = utils::app_config::AppConfig::merge_from_project_root(&project_root);
-    match app_config {
-        Ok(conf) => println!("config loaded: {:?}", conf),
-        Err(e) => eprintln!("error loading app config: {:?}", e)
+    // Remember the project for global-status
+    let registry = utils::registry::Registry::load(&REGISTRY_FILE_PATH).and_then(|mut registry| {
+        registry.record(&project_root);
+        registry.save(&REGISTRY_FILE_PATH)
+    });
+    if let Err(error) = registry {
+        eprintln!("Could not update the project registry ({})", error);
     }
 
-    // Find and read the docker `compose.yml` file
+    let app_config = match utils::app_config::AppConfig::load(&config_files.all()) {
+        Ok(app_config) => app_config,
+        Err(error) => {
+            eprintln!("{:#}", error);
+            sysexits::ExitCode::Config.exit()
+        }
+    };
+
+    // Find and read the docker compose files
     // TODO: Check if command requires knowledge of the compose config
-    let docker_compose_config_path = Path::new(&project_root).join("compose.yml");
-    let docker_compose = if docker_compose_config_path.is_file() {
-        utils::docker_compose::DockerCompose::new(docker_compose_config_path)
-    } else {
-        println!(
-            "Could not find a docker compose file in the project root ({})",
-            docker_compose_config_path.display()
-        );
-        sysexits::ExitCode::OsErr.exit()
+    let docker_compose = match utils::docker_compose::DockerCompose::from_config(&project_root, &app_config) {
+        Ok(docker_compose) => docker_compose,
+        Err(error) => {
+            eprintln!("{}", error);
+            sysexits::ExitCode::OsErr.exit()
+        }
     };
-    let _docker_compose_config = match docker_compose.config() {
+    let docker_compose_config = match docker_compose.config() {
         Ok(config) => config,
         Err(error) => {
             println!("Could not read the docker compose file ({})", error);
@@ -121,19 +157,61 @@ async fn main() -> Result<sysexits::ExitCode, Box<dyn std::error::Error>> {
     match cli.command {
         Some(command) => {
             match command {
-                Exec { service, user, command } => {
-                    commands::exec::run(docker_compose, service, user, command.to_vec())?
+                Exec { service, user, env, command } => {
+                    let args = commands::exec::ExecArgs { service, user, env, command };
+                    commands::exec::run(&docker, docker_compose, &docker_compose_config, &app_config, args).await?
+                }
+                Start { timeout } => {
+                    commands::start::run(&docker, docker_compose, &docker_compose_config, timeout).await?
+                }
+                ExportDb { file, gzip, zstd } => {
+                    let compression = match (gzip, zstd) {
+                        (true, _) => Some(utils::compression::Compression::Gzip),
+                        (_, true) => Some(utils::compression::Compression::Zstd),
+                        _ => None,
+                    };
+                    commands::export_db::run(docker_compose, &docker_compose_config, &app_config, &project_root, file, compression)?
+                }
+                ImportDb { file, drop } => {
+                    commands::import_db::run(docker_compose, &docker_compose_config, &app_config, &project_root, file, drop)?
+                }
+                Launch { print } => {
+                    commands::launch::run(&docker_compose_config, &app_config, print)?
+                }
+                Logs { services, follow, since, tail, grep, timestamps } => {
+                    let args = commands::logs::LogsArgs { services, follow, since, tail, grep, timestamps };
+                    commands::logs::run(&docker, &docker_compose_config, &app_config, args).await?
+                }
+                Run { name } => {
+                    commands::run::run(docker_compose, &docker_compose_config, &app_config, name)?
+                }
+                Restart { services, timeout } => {
+                    commands::restart::run(&docker, docker_compose, &docker_compose_config, &app_config, services, timeout).await?
+                }
+                Shell { service, user } => {
+                    commands::shell::run(docker_compose, &docker_compose_config, &app_config, service, user)?
+                }
+                Snapshot { command } => {
+                    commands::snapshot::run(docker_compose, &docker_compose_config, &app_config, &project_root, command)?
+                }
+                Status => {
+                    commands::status::run(&docker, &docker_compose_config).await?
+                }
+                Stop { remove_data, yes } => {
+                    commands::stop::run(&docker, docker_compose, &docker_compose_config, remove_data, yes).await?
                 }
-                //Stop { remove_data: false } => println!("Stopping without removing data..."),
-                //Stop { remove_data: true } => println!("Stopping with removing data..."),
                 _ => {
                     println!("Command not implemented yet: {:?}", command);
                     sysexits::ExitCode::OsErr.exit()
                 }
             }
         }
+        None if cli.exec_command.is_empty() => {
+            Cli::command().print_help()?;
+        }
         None => {
-            commands::exec::run(docker_compose, cli.service.to_owned(), None, cli.exec_command)?;
+            let args = commands::exec::ExecArgs { service: cli.service, user: None, env: Vec::new(), command: cli.exec_command };
+            commands::exec::run(&docker, docker_compose, &docker_compose_config, &app_config, args).await?;
         }
     }
 
@@ -1,50 +1,459 @@
-use std::fs::File;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
-use serde_merge::omerge;
-use anyhow::Result;
+use serde_yaml::{Mapping, Value};
+use anyhow::{anyhow, bail, Result};
+use schemars::JsonSchema;
+use crate::utils::suggest::did_you_mean;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct AppConfig {
+    /// The compose files of the project, relative to the project root. Later files override earlier ones.
+    /// Defaults to the file docker compose finds itself (e.g. compose.yaml) and its override file.
+    pub compose_files: Option<Vec<String>>,
+    /// The compose profiles to enable
+    pub compose_profiles: Option<Vec<String>>,
+    /// The compose project name, instead of the one docker compose derives from the directory
+    pub compose_project_name: Option<String>,
+    /// The compose service running the database
     pub database_container: Option<String>,
+    /// The compose service commands run in when no service is given. Defaults to
+    /// the service labeled `dev-cli.default: true`, else the first one.
+    pub default_service: Option<String>,
+    /// Where database dumps and snapshots are stored, relative to the project root
     pub dumps_dir: Option<String>,
+    /// What `dev-cli exec` does when the service isn't running. Defaults to `error`.
+    pub exec_when_stopped: Option<ExecWhenStopped>,
+    /// The URL `dev-cli launch` opens, instead of the one derived from the compose file
+    pub launch_url: Option<String>,
+    #[serde(rename = "run-commands", alias = "run_commands")]
+    pub run_commands: Option<BTreeMap<String, RunCommand>>,
+    /// Other names for compose services, e.g. `app: php`
+    pub service_aliases: Option<BTreeMap<String, String>>,
+    /// Settings per compose service, keyed by the service name
+    pub services: Option<BTreeMap<String, ServiceConfig>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceConfig {
+    /// The shell to start with `dev-cli shell` instead of detecting one
+    pub shell: Option<String>,
+    /// The user to run commands as
+    pub user: Option<String>,
+    /// The directory to run commands in when the current directory isn't mounted into the container
+    pub workdir: Option<String>,
+    /// Environment variables set for commands
+    pub env: Option<BTreeMap<String, String>>,
+}
+
+/// What `dev-cli exec` does when the container of the service isn't running
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecWhenStopped {
+    /// Start the service and the services it depends on first
+    Start,
+    /// Run the command in a new container of the service, removed afterwards
+    Run,
+    /// Fail with an error
+    Error,
+}
+
+/// A named recipe of commands, executed with `dev-cli run <name>`
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RunCommand {
+    pub description: Option<String>,
+    /// Run all steps at the same time instead of one after another
+    #[serde(default)]
+    pub parallel: bool,
+    #[serde(default)]
+    pub commands: Vec<RunCommandStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RunCommandStep {
+    /// The compose service to run the command in
+    pub container: String,
+    pub user: Option<String>,
+    /// Executed with `sh -c`, so it may contain pipes, globs, etc.
+    pub command: String,
 }
 
 impl std::default::Default for AppConfig {
     fn default() -> Self {
         AppConfig {
+            compose_files: None,
+            compose_profiles: None,
+            compose_project_name: None,
             database_container: Some(String::from("db")),
-            dumps_dir: Some(String::from("dumps"))
+            default_service: None,
+            dumps_dir: Some(String::from("dumps")),
+            exec_when_stopped: None,
+            launch_url: None,
+            run_commands: None,
+            service_aliases: None,
+            services: None,
         }
     }
 }
 
+/// The config files of a project, from the lowest to the highest precedence.
+/// Outside of a project, only the global config file exists.
+pub struct ConfigFiles {
+    pub global: PathBuf,
+    pub project: Option<PathBuf>,
+    pub local: Option<PathBuf>,
+}
+
+impl ConfigFiles {
+    pub fn all(&self) -> Vec<PathBuf> {
+        let mut files = vec![self.global.clone()];
+        files.extend(self.project.clone());
+        files.extend(self.local.clone());
+        files
+    }
+}
+
+/// Prefix of environment variables overriding config values. Nested keys are
+/// separated by two underscores, e.g. `DEV_CLI_SERVICES__PHP__USER`.
+const ENV_PREFIX: &str = "DEV_CLI_";
+
 impl AppConfig {
-    pub fn merge_from_project_root(
-        project_root: impl Into<PathBuf>
-    ) -> Result<Self> {
-        let project_root = project_root.into();
-        let mut config_file = project_root.clone();
-        config_file.push(".dev-cli.yml");
-        println!("config file: {:?}", &config_file);
-        let mut dist_config_file = project_root.clone();
-        dist_config_file.push("../../.dev-cli.dist.yml");
-        println!("dist config file: {:?}", &dist_config_file);
-
-        let config: AppConfig
-            = serde_yaml::from_reader(File::open(config_file)?)?;
-        println!("config: {:?}", &config);
-        let dist_config: AppConfig
-            = serde_yaml::from_reader(File::open(dist_config_file)?)?;
-        println!("dist config: {:?}", &dist_config);
-        let default_config = AppConfig::default();
-        println!("default config: {:?}", &default_config);
-
-        let merge_result  = omerge::<AppConfig, AppConfig, AppConfig>(
-            default_config,
-            omerge::<AppConfig, AppConfig, AppConfig>(dist_config, config)?,
-        )?;
-
-        Ok(merge_result)
+    /// Loads the config by layering the given files over the built-in
+    /// defaults, followed by the `DEV_CLI_*` environment variables. Later
+    /// layers override earlier ones, files that don't exist are skipped.
+    pub fn load(files: &[PathBuf]) -> Result<Self> {
+        Self::load_with_env(files, std::env::vars())
+    }
+
+    pub fn load_with_env(files: &[PathBuf], vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut merged = Value::Null;
+        for (_, layer) in Self::layers(files, vars)? {
+            merge(&mut merged, layer);
+        }
+        Ok(serde_yaml::from_value(merged)?)
+    }
+
+    /// Loads the config like [`AppConfig::load`], but returns it as a YAML
+    /// value without unset keys, together with the layer every value comes
+    /// from, keyed by its dotted path (e.g. `services.php.user`).
+    pub fn resolve(files: &[PathBuf]) -> Result<(Value, BTreeMap<String, String>)> {
+        Self::resolve_with_env(files, std::env::vars())
+    }
+
+    pub fn resolve_with_env(files: &[PathBuf], vars: impl IntoIterator<Item = (String, String)>) -> Result<(Value, BTreeMap<String, String>)> {
+        let layers = Self::layers(files, vars)?;
+        let mut merged = Value::Null;
+        for (_, layer) in &layers {
+            merge(&mut merged, layer.clone());
+        }
+        // Going through the struct adds the defaults of nested values, e.g. `parallel`
+        let config: AppConfig = serde_yaml::from_value(merged)?;
+        let mut value = serde_yaml::to_value(config)?;
+        remove_nulls(&mut value);
+
+        let mut paths = Vec::new();
+        leaf_paths(&value, "", &mut paths);
+        let origins = paths
+            .into_iter()
+            .map(|path| {
+                let origin = layers
+                    .iter()
+                    .rev()
+                    .find(|(_, layer)| get(layer, &path).map(|value| !value.is_null()).unwrap_or(false))
+                    .map(|(origin, _)| origin.clone())
+                    .unwrap_or(String::from("defaults"));
+                (path, origin)
+            })
+            .collect();
+
+        Ok((value, origins))
+    }
+
+    /// Reads every layer of the config, together with where it comes from.
+    fn layers(files: &[PathBuf], vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<(String, Value)>> {
+        let mut layers = vec![(String::from("defaults"), serde_yaml::to_value(AppConfig::default())?)];
+
+        for file in files.iter().filter(|file| file.is_file()) {
+            if let Some(layer) = Self::parse_file(file)? {
+                layers.push((file.display().to_string(), normalize(layer)));
+            }
+        }
+
+        for (name, value) in vars {
+            let path = match name.strip_prefix(ENV_PREFIX) {
+                Some(path) if !path.is_empty() => path.to_lowercase(),
+                _ => continue,
+            };
+            // Values are parsed like in the config files, so lists etc. work too
+            let mut layer = serde_yaml::from_str(&value).unwrap_or(Value::String(value));
+            for key in path.rsplit("__") {
+                let mut mapping = Mapping::new();
+                mapping.insert(Value::String(key.to_string()), layer);
+                layer = Value::Mapping(mapping);
+            }
+            let layer = normalize(layer);
+            if let Err(error) = serde_yaml::from_value::<AppConfig>(layer.clone()) {
+                // Other tools may use the prefix too (e.g. DEV_CLI_VERSION), so
+                // unknown keys are skipped and only invalid values are rejected
+                let message = describe(&error);
+                if message.contains("unknown field `") {
+                    eprintln!("Ignoring {}: {}", name, message);
+                    continue;
+                }
+                bail!("{}: {}", name, message);
+            }
+            layers.push((name, layer));
+        }
+
+        Ok(layers)
+    }
+
+    /// Reads a config file, rejecting unknown keys and values of the wrong
+    /// type with their location in the file. Empty files return nothing.
+    pub fn parse_file(file: &Path) -> Result<Option<Value>> {
+        let content = std::fs::read_to_string(file).map_err(|error| anyhow!("Could not read {} ({})", file.display(), error))?;
+        let layer: Value = serde_yaml::from_str(&content).map_err(|error| file_error(file, &error))?;
+        match layer {
+            Value::Null => Ok(None),
+            Value::Mapping(_) => {
+                serde_yaml::from_str::<AppConfig>(&content).map_err(|error| file_error(file, &error))?;
+                Ok(Some(layer))
+            }
+            _ => bail!("Could not parse {}, the config has to be a mapping of keys to values", file.display()),
+        }
+    }
+
+    /// The directory database dumps are stored in, relative paths are
+    /// relative to the project root.
+    pub fn dumps_dir(&self, project_root: &Path) -> PathBuf {
+        project_root.join(self.dumps_dir.as_deref().unwrap_or("dumps"))
+    }
+
+    pub fn service(&self, name: &str) -> Option<&ServiceConfig> {
+        self.services.as_ref().and_then(|services| services.get(name))
+    }
+
+    /// The environment variables of a service from the config, overridden by
+    /// the given ones.
+    pub fn service_env(&self, name: &str, overrides: Vec<(String, String)>) -> Vec<(String, String)> {
+        let mut env = self.service(name).and_then(|service| service.env.clone()).unwrap_or_default();
+        env.extend(overrides);
+        env.into_iter().collect()
+    }
+}
+
+/// Formats an error as `file:line:column: message`
+fn file_error(file: &Path, error: &serde_yaml::Error) -> anyhow::Error {
+    match error.location() {
+        Some(location) => anyhow!("Could not parse {}:{}:{}: {}", file.display(), location.line(), location.column(), describe(error)),
+        None => anyhow!("Could not parse {}: {}", file.display(), describe(error)),
+    }
+}
+
+/// The message of a YAML error without its location, suggesting the closest
+/// key for unknown ones.
+fn describe(error: &serde_yaml::Error) -> String {
+    let message = error.to_string();
+    let message = match error.location() {
+        Some(location) => message
+            .trim_end_matches(&format!(" at line {} column {}", location.line(), location.column()))
+            .to_string(),
+        None => message,
+    };
+
+    // e.g. "services.php: unknown field `usr`, expected `shell` or `user`"
+    let suggestion = message.split_once("unknown field `").and_then(|(_, rest)| {
+        let (unknown, expected) = rest.split_once('`')?;
+        let candidates: Vec<&str> = expected.split('`').skip(1).step_by(2).collect();
+        did_you_mean(unknown, &candidates).map(|suggestion| (unknown.to_string(), suggestion.to_string()))
+    });
+    match suggestion {
+        Some((unknown, suggestion)) => format!(
+            "{}unknown field `{}`, did you mean `{}`?",
+            message.split("unknown field `").next().unwrap_or_default(),
+            unknown,
+            suggestion
+        ),
+        None => message,
+    }
+}
+
+/// Renames keys given by their alias, so they are merged with the same key
+/// of the other layers.
+fn normalize(mut layer: Value) -> Value {
+    if let Value::Mapping(mapping) = &mut layer {
+        if let Some(run_commands) = mapping.remove("run_commands") {
+            mapping.insert(Value::from("run-commands"), run_commands);
+        }
+    }
+    layer
+}
+
+/// Looks up a value by its dotted path, e.g. `services.php.user`
+pub fn get<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+pub fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn remove_nulls(value: &mut Value) {
+    if let Value::Mapping(mapping) = value {
+        mapping.retain(|_, value| !value.is_null());
+        mapping.values_mut().for_each(remove_nulls);
+    }
+}
+
+/// Collects the dotted paths of all values that aren't mappings
+fn leaf_paths(value: &Value, prefix: &str, paths: &mut Vec<String>) {
+    match value {
+        Value::Mapping(mapping) if !mapping.is_empty() => {
+            for (key, value) in mapping {
+                let path = join_path(prefix, key.as_str().unwrap_or_default());
+                leaf_paths(value, &path, paths);
+            }
+        }
+        _ => paths.push(prefix.to_string()),
+    }
+}
+
+/// Merges a layer into the config, mappings are merged key by key while all
+/// other values replace the previous value. Null never overrides a value.
+fn merge(config: &mut Value, layer: Value) {
+    match (config, layer) {
+        (_, Value::Null) => {}
+        (Value::Mapping(config), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                match config.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        config.insert(key, value);
+                    }
+                }
+            }
+        }
+        (config, layer) => *config = layer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overwrite_config_example() -> Result<()> {
+        let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/overwrite-config");
+        let files = [
+            example.join("global.dev-cli.yml"),
+            example.join(".dev-cli.dist.yml"),
+            example.join(".dev-cli.yml"),
+            example.join("does-not-exist.yml"),
+        ];
+        let vars = [
+            (String::from("DEV_CLI_LAUNCH_URL"), String::from("https://env.localhost")),
+            (String::from("DEV_CLI_SERVICES__NGINX__USER"), String::from("root")),
+            (String::from("UNRELATED"), String::from("value")),
+        ];
+
+        let config = AppConfig::load_with_env(&files, vars)?;
+        // Only set by the defaults
+        assert!(config.run_commands.is_none());
+        // Overwritten by the project config
+        assert_eq!(config.database_container.as_deref(), Some("project-db"));
+        // Overwritten by the local config
+        assert_eq!(config.dumps_dir.as_deref(), Some("local-dumps"));
+        // Overwritten by environment variables
+        assert_eq!(config.launch_url.as_deref(), Some("https://env.localhost"));
+        let nginx = config.service("nginx").unwrap();
+        assert_eq!(nginx.user.as_deref(), Some("root"));
+        assert_eq!(nginx.shell.as_deref(), Some("bash"));
+        Ok(())
+    }
+
+    #[test]
+    fn origins_of_values() -> Result<()> {
+        let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/overwrite-config");
+        let files = [example.join("global.dev-cli.yml"), example.join(".dev-cli.dist.yml"), example.join(".dev-cli.yml")];
+        let vars = [(String::from("DEV_CLI_SERVICES__NGINX__USER"), String::from("root"))];
+
+        let (value, origins) = AppConfig::resolve_with_env(&files, vars)?;
+        assert_eq!(get(&value, "services.nginx.user"), Some(&Value::from("root")));
+        assert_eq!(get(&value, "run-commands"), None);
+        assert_eq!(origins["services.nginx.user"], "DEV_CLI_SERVICES__NGINX__USER");
+        assert_eq!(origins["services.nginx.shell"], files[0].display().to_string());
+        assert_eq!(origins["database_container"], files[1].display().to_string());
+        assert_eq!(origins["dumps_dir"], files[2].display().to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn reject_unknown_keys_with_location() -> Result<()> {
+        let file = assert_fs::NamedTempFile::new(".dev-cli.yml")?;
+        std::fs::write(file.path(), "services:\n  php:\n    usr: root\n")?;
+        let error = AppConfig::load_with_env(&[file.path().to_path_buf()], [])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            format!("Could not parse {}:3:5: services.php: unknown field `usr`, did you mean `user`?", file.path().display())
+        );
+
+        std::fs::write(file.path(), "run-commands:\n  test:\n    parallel: yes please\n")?;
+        let error = AppConfig::load_with_env(&[file.path().to_path_buf()], [])
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with(&format!("Could not parse {}:3:15: ", file.path().display())), "{}", error);
+
+        let vars = [
+            (String::from("DEV_CLI_DATABASE_CONTANER"), String::from("mysql")),
+            (String::from("DEV_CLI_VERSION"), String::from("1.2.3")),
+        ];
+        let config = AppConfig::load_with_env(&[], vars)?;
+        assert_eq!(config.database_container.as_deref(), Some("db"));
+
+        let vars = [(String::from("DEV_CLI_SERVICES__PHP__USER"), String::from("[root]"))];
+        let error = AppConfig::load_with_env(&[], vars).unwrap_err().to_string();
+        assert!(error.starts_with("DEV_CLI_SERVICES__PHP__USER: invalid type"), "{}", error);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_files_use_defaults() -> Result<()> {
+        let config = AppConfig::load_with_env(&[PathBuf::from("/does/not/exist.yml")], [])?;
+        assert_eq!(config.database_container.as_deref(), Some("db"));
+        assert_eq!(config.dumps_dir.as_deref(), Some("dumps"));
+        Ok(())
+    }
+
+    #[test]
+    fn service_env_is_overridden() -> Result<()> {
+        let config: AppConfig = serde_yaml::from_str("services: {php: {env: {APP_ENV: dev, XDEBUG_MODE: debug}}}")?;
+        let env = config.service_env("php", vec![(String::from("APP_ENV"), String::from("test"))]);
+        assert_eq!(env, vec![
+            (String::from("APP_ENV"), String::from("test")),
+            (String::from("XDEBUG_MODE"), String::from("debug")),
+        ]);
+        assert!(config.service_env("node", Vec::new()).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn exec_when_stopped_from_env() -> Result<()> {
+        let vars = [(String::from("DEV_CLI_EXEC_WHEN_STOPPED"), String::from("run"))];
+        let config = AppConfig::load_with_env(&[], vars)?;
+        assert_eq!(config.exec_when_stopped, Some(ExecWhenStopped::Run));
+
+        let vars = [(String::from("DEV_CLI_EXEC_WHEN_STOPPED"), String::from("restart"))];
+        assert!(AppConfig::load_with_env(&[], vars).is_err());
+        Ok(())
     }
 }
@@ -0,0 +1,785 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde_yaml::{Mapping, Value};
+use crate::utils::docker_compose::{self, ServiceVolume};
+
+/// Reads compose files the way `docker compose config` does: variables are
+/// interpolated from the environment and the `.env` file, the files are merged
+/// in order and short syntax is normalised to the long syntax. Everything
+/// dev-cli doesn't need to understand is passed through unchanged.
+pub fn load(files: &[PathBuf], profiles: &[String], project_name: Option<&str>, environment: impl IntoIterator<Item = (String, String)>) -> Result<Value, String> {
+    // Relative paths in all files are relative to the directory of the first one
+    let base = files
+        .first()
+        .and_then(|file| file.parent())
+        .ok_or(String::from("No compose file given"))?
+        .to_path_buf();
+
+    let mut variables = read_env_file(&base.join(".env"))?;
+    // Variables of the shell take precedence over the .env file
+    variables.extend(environment);
+
+    let mut documents = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(file).map_err(|error| format!("Could not read {} ({})", file.display(), error))?;
+        let document: Value = serde_yaml::from_str(&content).map_err(|error| format!("{}: {}", file.display(), error))?;
+        documents.push((file, document));
+    }
+
+    let name = match project_name.map(String::from).or(variables.get("COMPOSE_PROJECT_NAME").cloned()) {
+        Some(name) => name,
+        None => match documents.iter().rev().find_map(|(_, document)| document.get("name")?.as_str()) {
+            Some(name) => interpolate(name, &variables)?,
+            None => base.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        },
+    };
+    let name = normalize_project_name(&name);
+    variables.entry(String::from("COMPOSE_PROJECT_NAME")).or_insert(name.clone());
+
+    let mut profiles = profiles.to_vec();
+    if let Some(from_environment) = variables.get("COMPOSE_PROFILES") {
+        profiles.extend(from_environment.split(',').map(str::trim).filter(|profile| !profile.is_empty()).map(String::from));
+    }
+
+    let mut merged = Value::Mapping(Mapping::new());
+    for (file, document) in documents {
+        let document = match document {
+            Value::Null => continue,
+            Value::Mapping(_) => document,
+            _ => return Err(format!("{}: the compose file has to be a mapping", file.display())),
+        };
+        check_supported(&document).map_err(|error| format!("{}: {}", file.display(), error))?;
+        let document = interpolate_value(document, &variables).map_err(|error| format!("{}: {}", file.display(), error))?;
+        let mut document = normalize_file(document, &base, &variables).map_err(|error| format!("{}: {}", file.display(), error))?;
+        apply_extends(&mut document, &base, &variables).map_err(|error| format!("{}: {}", file.display(), error))?;
+        merge(&mut merged, document, &[]);
+    }
+    finalize(&mut merged, &name, &profiles);
+
+    Ok(merged)
+}
+
+/// Top-level keys the loader understands. Extensions (`x-*`) are passed through.
+const TOP_LEVEL_KEYS: [&str; 7] = ["version", "name", "services", "networks", "volumes", "secrets", "configs"];
+
+/// Fails for anything the loader would silently get wrong, like `include`, so
+/// `docker compose config` is used instead.
+fn check_supported(document: &Value) -> Result<(), String> {
+    for key in document.as_mapping().into_iter().flat_map(Mapping::keys) {
+        let key = key.as_str().unwrap_or_default();
+        if !key.starts_with("x-") && !TOP_LEVEL_KEYS.contains(&key) {
+            return Err(format!("{} is not supported", key));
+        }
+    }
+    Ok(())
+}
+
+/// Project names may only contain lowercase letters, digits, dashes and underscores.
+fn normalize_project_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|char| char.is_ascii_alphanumeric() || *char == '-' || *char == '_')
+        .collect::<String>()
+        .trim_start_matches(['-', '_'])
+        .to_string()
+}
+
+/// Reads the `KEY=VALUE` lines of an env file, a missing file has no variables.
+pub fn read_env_file(file: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut variables = BTreeMap::new();
+    if !file.is_file() {
+        return Ok(variables);
+    }
+
+    let content = std::fs::read_to_string(file).map_err(|error| format!("Could not read {} ({})", file.display(), error))?;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=').unwrap_or((line, ""));
+        let value = value.trim();
+        let value = if value.len() >= 2 && (value.starts_with('"') && value.ends_with('"') || value.starts_with('\'') && value.ends_with('\'')) {
+            value[1..value.len() - 1].to_string()
+        } else {
+            // Unquoted values end at a comment
+            match value.find(" #") {
+                Some(index) => value[..index].trim_end().to_string(),
+                None => value.to_string(),
+            }
+        };
+        variables.insert(key.trim().to_string(), value);
+    }
+
+    Ok(variables)
+}
+
+/// Replaces `$VAR`, `${VAR}` and the `${VAR:-default}`, `${VAR-default}`,
+/// `${VAR:?error}`, `${VAR?error}`, `${VAR:+replacement}` and
+/// `${VAR+replacement}` forms. `$$` is a literal `$`. Unset variables are empty.
+pub fn interpolate(input: &str, variables: &BTreeMap<String, String>) -> Result<String, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        if chars[index] != '$' || index + 1 == chars.len() {
+            output.push(chars[index]);
+            index += 1;
+            continue;
+        }
+
+        match chars[index + 1] {
+            '$' => {
+                output.push('$');
+                index += 2;
+            }
+            '{' => {
+                let mut depth = 0;
+                let end = (index + 1..chars.len())
+                    .find(|&end| {
+                        match chars[end] {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0
+                    })
+                    .ok_or(format!("invalid interpolation format for \"{}\"", input))?;
+                let expression: String = chars[index + 2..end].iter().collect();
+                output.push_str(&resolve(&expression, variables)?);
+                index = end + 1;
+            }
+            char if char.is_ascii_alphabetic() || char == '_' => {
+                let end = (index + 1..chars.len())
+                    .find(|&end| !(chars[end].is_ascii_alphanumeric() || chars[end] == '_'))
+                    .unwrap_or(chars.len());
+                let name: String = chars[index + 1..end].iter().collect();
+                output.push_str(variables.get(&name).map(String::as_str).unwrap_or_default());
+                index = end;
+            }
+            _ => {
+                output.push('$');
+                index += 1;
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Resolves the expression inside of `${...}`
+fn resolve(expression: &str, variables: &BTreeMap<String, String>) -> Result<String, String> {
+    let name_length = expression
+        .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_'))
+        .unwrap_or(expression.len());
+    let (name, modifier) = expression.split_at(name_length);
+    if name.is_empty() {
+        return Err(format!("invalid interpolation format for \"${{{}}}\"", expression));
+    }
+
+    let value = variables.get(name);
+    let non_empty = value.filter(|value| !value.is_empty());
+    let operators = [":-", ":?", ":+", "-", "?", "+"];
+    let operator = match operators.iter().find(|operator| modifier.starts_with(**operator)) {
+        Some(operator) => *operator,
+        None if modifier.is_empty() => return Ok(value.cloned().unwrap_or_default()),
+        None => return Err(format!("invalid interpolation format for \"${{{}}}\"", expression)),
+    };
+    let argument = &modifier[operator.len()..];
+
+    match operator {
+        ":-" => non_empty.cloned().map(Ok).unwrap_or_else(|| interpolate(argument, variables)),
+        "-" => value.cloned().map(Ok).unwrap_or_else(|| interpolate(argument, variables)),
+        ":?" => non_empty.cloned().ok_or(format!("required variable {} is missing a value: {}", name, argument)),
+        "?" => value.cloned().ok_or(format!("required variable {} is missing a value: {}", name, argument)),
+        ":+" => non_empty.map(|_| interpolate(argument, variables)).unwrap_or(Ok(String::new())),
+        _ => value.map(|_| interpolate(argument, variables)).unwrap_or(Ok(String::new())),
+    }
+}
+
+/// Interpolates all string values, keys are left as they are.
+fn interpolate_value(value: Value, variables: &BTreeMap<String, String>) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(string) => Value::String(interpolate(&string, variables)?),
+        Value::Sequence(sequence) => Value::Sequence(
+            sequence.into_iter().map(|value| interpolate_value(value, variables)).collect::<Result<_, _>>()?,
+        ),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| Ok((key, interpolate_value(value, variables)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        value => value,
+    })
+}
+
+/// Converts the short syntax of a file to the long syntax, so files can be
+/// merged field by field.
+fn normalize_file(mut document: Value, base: &Path, variables: &BTreeMap<String, String>) -> Result<Value, String> {
+    if let Some(Value::Mapping(services)) = document.get_mut("services") {
+        for (name, service) in services.iter_mut() {
+            if service.is_null() {
+                *service = Value::Mapping(Mapping::new());
+            }
+            let name = name.as_str().unwrap_or_default();
+            let service = service.as_mapping_mut().ok_or(format!("services.{} has to be a mapping", name))?;
+            normalize_service(service, base, variables).map_err(|error| format!("services.{}.{}", name, error))?;
+        }
+    }
+
+    if let Some(Value::Mapping(secrets)) = document.get_mut("secrets") {
+        for secret in secrets.values_mut() {
+            if let Some(Value::String(file)) = secret.get_mut("file") {
+                *file = absolute(base, file);
+            }
+        }
+    }
+
+    Ok(document)
+}
+
+fn normalize_service(service: &mut Mapping, base: &Path, variables: &BTreeMap<String, String>) -> Result<(), String> {
+    if let Some(build) = service.get_mut("build") {
+        if let Value::String(context) = build {
+            let mut long = Mapping::new();
+            long.insert(Value::from("context"), Value::String(context.clone()));
+            *build = Value::Mapping(long);
+        }
+        if let Some(Value::String(context)) = build.get_mut("context") {
+            // Git repositories and other remote contexts stay as they are
+            if !context.contains("://") && !context.starts_with("git@") {
+                *context = absolute(base, context);
+            }
+        }
+        if let Value::Mapping(build) = build {
+            build.entry(Value::from("dockerfile")).or_insert(Value::from("Dockerfile"));
+        }
+    }
+
+    let mut environment = match service.remove("environment") {
+        Some(environment) => key_values(environment).map_err(|error| format!("environment: {}", error))?,
+        None => BTreeMap::new(),
+    };
+    // Variables without a value are taken from the shell, and left out if unset
+    let mut environment: BTreeMap<String, String> = environment
+        .iter_mut()
+        .filter_map(|(key, value)| Some((key.clone(), value.take().or(variables.get(key).cloned())?)))
+        .collect();
+    if let Some(env_files) = service.remove("env_file") {
+        let env_files = match env_files {
+            Value::Sequence(env_files) => env_files,
+            env_file => vec![env_file],
+        };
+        for env_file in env_files {
+            let (path, required) = match &env_file {
+                Value::String(path) => (path.as_str(), true),
+                Value::Mapping(_) => (
+                    env_file.get("path").and_then(Value::as_str).unwrap_or_default(),
+                    env_file.get("required").and_then(Value::as_bool).unwrap_or(true),
+                ),
+                _ => return Err(String::from("env_file: has to be a path or a list of paths")),
+            };
+            let path = PathBuf::from(absolute(base, path));
+            if !path.is_file() && required {
+                return Err(format!("env_file: {} does not exist", path.display()));
+            }
+            // The environment of the service takes precedence over env files
+            for (key, value) in read_env_file(&path)? {
+                environment.entry(key).or_insert(value);
+            }
+        }
+    }
+    if !environment.is_empty() {
+        service.insert(Value::from("environment"), string_map(environment));
+    }
+
+    if let Some(labels) = service.get_mut("labels") {
+        let labels_map = key_values(labels.clone()).map_err(|error| format!("labels: {}", error))?;
+        *labels = string_map(labels_map.into_iter().map(|(key, value)| (key, value.unwrap_or_default())).collect());
+    }
+
+    if let Some(depends_on) = service.get_mut("depends_on") {
+        let mut long = Mapping::new();
+        match depends_on {
+            Value::Sequence(names) => {
+                for name in names.iter() {
+                    long.insert(name.clone(), Value::Mapping(Mapping::new()));
+                }
+            }
+            Value::Mapping(mapping) => long = mapping.clone(),
+            _ => return Err(String::from("depends_on: has to be a list or a mapping")),
+        }
+        for dependency in long.values_mut() {
+            if dependency.is_null() {
+                *dependency = Value::Mapping(Mapping::new());
+            }
+            if let Value::Mapping(dependency) = dependency {
+                dependency.entry(Value::from("condition")).or_insert(Value::from("service_started"));
+                dependency.entry(Value::from("required")).or_insert(Value::from(true));
+            }
+        }
+        *depends_on = Value::Mapping(long);
+    }
+
+    if let Some(Value::Sequence(networks)) = service.get("networks") {
+        let networks = networks.iter().map(|network| (network.clone(), Value::Null)).collect();
+        service.insert(Value::from("networks"), Value::Mapping(networks));
+    }
+
+    if let Some(ports) = service.get_mut("ports") {
+        let long = docker_compose::ports(ports.clone()).map_err(|error| format!("ports: {}", error))?;
+        *ports = serde_yaml::to_value(long).map_err(|error| format!("ports: {}", error))?;
+    }
+
+    if let Some(Value::Sequence(volumes)) = service.get_mut("volumes") {
+        for volume in volumes.iter_mut() {
+            *volume = normalize_volume(volume, base).map_err(|error| format!("volumes: {}", error))?;
+        }
+    }
+
+    if let Some(Value::Sequence(secrets)) = service.get_mut("secrets") {
+        for secret in secrets.iter_mut() {
+            if let Value::String(source) = secret {
+                let mut long = Mapping::new();
+                long.insert(Value::from("source"), Value::String(source.clone()));
+                *secret = Value::Mapping(long);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the `extends` of all services with the service they extend,
+/// overridden by the service itself.
+fn apply_extends(document: &mut Value, directory: &Path, variables: &BTreeMap<String, String>) -> Result<(), String> {
+    let names: Vec<String> = document
+        .get("services")
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flat_map(Mapping::keys)
+        .filter_map(|name| name.as_str().map(String::from))
+        .collect();
+    for name in names {
+        extend_service(document, &name, None, directory, variables, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+/// Resolves the `extends` of a single service, including the services it
+/// extends in turn. `file` is the file of the document if it was only read
+/// for being extended, `seen` holds the chain of services to detect cycles.
+fn extend_service(document: &mut Value, name: &str, file: Option<&Path>, directory: &Path, variables: &BTreeMap<String, String>, seen: &mut Vec<String>) -> Result<(), String> {
+    let extends = match document.get_mut("services").and_then(|services| services.get_mut(name)) {
+        Some(Value::Mapping(service)) => service.remove("extends"),
+        Some(_) => None,
+        None => return Err(format!("the extended service {} does not exist", name)),
+    };
+    let Some(extends) = extends else { return Ok(()) };
+
+    let reference = |file: Option<&Path>, name: &str| format!("{}:{}", file.map(|file| file.display().to_string()).unwrap_or_default(), name);
+    seen.push(reference(file, name));
+
+    let (base_name, base_file) = match &extends {
+        Value::String(base_name) => (base_name.clone(), None),
+        Value::Mapping(_) => (
+            extends.get("service").and_then(Value::as_str).ok_or(format!("services.{}.extends: service is required", name))?.to_string(),
+            extends.get("file").and_then(Value::as_str).map(|file| PathBuf::from(absolute(directory, file))),
+        ),
+        _ => return Err(format!("services.{}.extends: has to be a service name or a mapping", name)),
+    };
+    let base_file = base_file.or(file.map(Path::to_path_buf));
+    if seen.contains(&reference(base_file.as_deref(), &base_name)) {
+        return Err(format!("services.{}.extends: circular reference to {}", name, base_name));
+    }
+
+    let mut extended = match base_file.filter(|base_file| Some(base_file.as_path()) != file) {
+        None => {
+            extend_service(document, &base_name, file, directory, variables, seen)?;
+            document["services"][base_name.as_str()].clone()
+        }
+        Some(file) => {
+            // Relative paths of the other file are relative to its own directory
+            let file_directory = file.parent().unwrap_or(directory).to_path_buf();
+            let content = std::fs::read_to_string(&file).map_err(|error| format!("Could not read {} ({})", file.display(), error))?;
+            let other: Value = serde_yaml::from_str(&content).map_err(|error| format!("{}: {}", file.display(), error))?;
+            let other = interpolate_value(other, variables).map_err(|error| format!("{}: {}", file.display(), error))?;
+            let mut other = normalize_file(other, &file_directory, variables).map_err(|error| format!("{}: {}", file.display(), error))?;
+            extend_service(&mut other, &base_name, Some(&file), &file_directory, variables, seen).map_err(|error| format!("{}: {}", file.display(), error))?;
+            other["services"][base_name.as_str()].clone()
+        }
+    };
+    let service = document["services"][name].clone();
+    merge(&mut extended, service, &["services", name]);
+    document["services"][name] = extended;
+    Ok(())
+}
+
+/// Reads a list of `KEY=VALUE` strings or a mapping, as used for the
+/// environment and labels. Keys without a value map to `None`.
+pub fn key_values(value: Value) -> Result<BTreeMap<String, Option<String>>, String> {
+    match value {
+        Value::Null => Ok(BTreeMap::new()),
+        Value::Sequence(entries) => entries
+            .into_iter()
+            .map(|entry| {
+                let entry = scalar_to_string(&entry).ok_or(String::from("entries have to be strings"))?;
+                Ok(match entry.split_once('=') {
+                    Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                    None => (entry, None),
+                })
+            })
+            .collect(),
+        Value::Mapping(mapping) => mapping
+            .into_iter()
+            .map(|(key, value)| {
+                let key = scalar_to_string(&key).ok_or(String::from("keys have to be strings"))?;
+                let value = match value {
+                    Value::Null => None,
+                    value => Some(scalar_to_string(&value).ok_or(format!("the value of {} has to be a string", key))?),
+                };
+                Ok((key, value))
+            })
+            .collect(),
+        _ => Err(String::from("has to be a list or a mapping")),
+    }
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Bool(bool) => Some(bool.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn string_map(map: BTreeMap<String, String>) -> Value {
+    Value::Mapping(map.into_iter().map(|(key, value)| (Value::String(key), Value::String(value))).collect())
+}
+
+/// Resolves a path relative to the project directory, also expanding `~`.
+fn absolute(base: &Path, path: &str) -> String {
+    let path = match path.strip_prefix("~/") {
+        Some(path) => dirs::home_dir().map(|home| home.join(path)).unwrap_or(PathBuf::from(path)),
+        None => PathBuf::from(path),
+    };
+    let mut absolute = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    absolute.to_string_lossy().to_string()
+}
+
+/// Converts a volume like "./src:/app:ro" into the long syntax. Relative bind
+/// mount sources become absolute, volumes already in the long syntax are kept
+/// as they are otherwise, including keys the model doesn't know like `tmpfs`.
+fn normalize_volume(volume: &Value, base: &Path) -> Result<Value, String> {
+    match volume {
+        Value::String(short) => {
+            let mut volume: ServiceVolume = short.parse()?;
+            if volume.volume_type == "bind" {
+                volume.source = absolute(base, &volume.source);
+            }
+            serde_yaml::to_value(volume).map_err(|error| error.to_string())
+        }
+        Value::Mapping(long) => {
+            let mut long = long.clone();
+            if long.get("type").and_then(Value::as_str) == Some("bind") {
+                if let Some(source) = long.get("source").and_then(Value::as_str) {
+                    let source = absolute(base, source);
+                    long.insert(Value::from("source"), Value::from(source));
+                }
+            }
+            Ok(Value::Mapping(long))
+        }
+        other => Err(format!("expected a string or a mapping, found {:?}", other)),
+    }
+}
+
+/// Merges an override file into the previous files. Mappings are merged key
+/// by key, lists of ports are combined, and volumes and secrets replace the
+/// entry with the same target or source. All other lists are replaced.
+fn merge(base: &mut Value, layer: Value, path: &[&str]) {
+    match (base, layer) {
+        (base, Value::Null) if !base.is_null() => {}
+        (Value::Mapping(base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                let mut path = path.to_vec();
+                path.push(key.as_str().unwrap_or_default());
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value, &path),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(base), Value::Sequence(layer)) if path.len() == 3 && path[0] == "services" => {
+            let identity = match path[2] {
+                "volumes" => Some("target"),
+                "secrets" => Some("source"),
+                "ports" => None,
+                _ => {
+                    *base = layer;
+                    return;
+                }
+            };
+            for value in layer {
+                let existing = base.iter_mut().find(|existing| match identity {
+                    Some(identity) => existing.get(identity).is_some() && existing.get(identity) == value.get(identity),
+                    None => **existing == value,
+                });
+                match existing {
+                    Some(existing) => *existing = value,
+                    None => base.push(value),
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Adds what docker compose derives from the merged files: the project name,
+/// the default network and the names of networks, volumes and secrets. Also
+/// removes services of profiles that aren't enabled.
+fn finalize(config: &mut Value, name: &str, profiles: &[String]) {
+    let Value::Mapping(config) = config else { return };
+    config.remove("version");
+    config.remove("name");
+    // The name comes first, like in the output of `docker compose config`
+    let mut with_name = Mapping::new();
+    with_name.insert(Value::from("name"), Value::from(name));
+    with_name.extend(std::mem::take(config));
+    *config = with_name;
+
+    let services = config.entry(Value::from("services")).or_insert(Value::Mapping(Mapping::new()));
+    if let Value::Mapping(services) = services {
+        services.retain(|_, service| match service.get("profiles").and_then(Value::as_sequence) {
+            Some(service_profiles) if !service_profiles.is_empty() => service_profiles
+                .iter()
+                .any(|profile| profile.as_str().map(|profile| profiles.iter().any(|enabled| enabled == profile)).unwrap_or(false)),
+            _ => true,
+        });
+        for service in services.values_mut() {
+            if let Value::Mapping(service) = service {
+                if !service.contains_key("network_mode") && !service.contains_key("networks") {
+                    let mut networks = Mapping::new();
+                    networks.insert(Value::from("default"), Value::Null);
+                    service.insert(Value::from("networks"), Value::Mapping(networks));
+                }
+            }
+        }
+    }
+    let uses_default_network = config
+        .get("services")
+        .and_then(Value::as_mapping)
+        .map(|services| services.values().any(|service| service.get("networks").and_then(|networks| networks.get("default")).is_some()))
+        .unwrap_or(false);
+    if uses_default_network {
+        let networks = config.entry(Value::from("networks")).or_insert(Value::Mapping(Mapping::new()));
+        if let Value::Mapping(networks) = networks {
+            networks.entry(Value::from("default")).or_insert(Value::Null);
+        }
+    }
+
+    for section in ["networks", "volumes", "secrets"] {
+        let Some(Value::Mapping(definitions)) = config.get_mut(section) else { continue };
+        for (key, definition) in definitions.iter_mut() {
+            if definition.is_null() {
+                *definition = Value::Mapping(Mapping::new());
+            }
+            let Value::Mapping(definition) = definition else { continue };
+            let key = key.as_str().unwrap_or_default();
+            // External resources are managed outside of the project, so their name isn't prefixed
+            let default_name = match definition.get("external").and_then(Value::as_bool) {
+                Some(true) => key.to_string(),
+                _ => format!("{}_{}", name, key),
+            };
+            definition.entry(Value::from("name")).or_insert(Value::String(default_name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::docker_compose::Config;
+
+    fn variables(variables: &[(&str, &str)]) -> BTreeMap<String, String> {
+        variables.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn interpolation() {
+        let variables = variables(&[("TLD", "test"), ("EMPTY", ""), ("PORT", "8080")]);
+        let interpolate = |input: &str| interpolate(input, &variables);
+        assert_eq!(interpolate("app.${TLD} $TLD $$TLD"), Ok(String::from("app.test test $TLD")));
+        assert_eq!(interpolate("${UNSET}|${EMPTY:-default}|${EMPTY-default}|${UNSET-default}"), Ok(String::from("|default||default")));
+        assert_eq!(interpolate("${UNSET:-${PORT:-80}}"), Ok(String::from("8080")));
+        assert_eq!(interpolate("${PORT:+set}|${EMPTY:+set}|${EMPTY+set}"), Ok(String::from("set||set")));
+        assert_eq!(interpolate("${EMPTY?required}"), Ok(String::new()));
+        assert_eq!(interpolate("${EMPTY:?is required}"), Err(String::from("required variable EMPTY is missing a value: is required")));
+        assert!(interpolate("${TLD").is_err());
+        assert!(interpolate("${TLD!}").is_err());
+    }
+
+    #[test]
+    fn short_syntax() {
+        let base = Path::new("/project");
+        let volume = |volume: &str| serde_yaml::to_string(&normalize_volume(&Value::from(volume), base).unwrap()).unwrap();
+        assert_eq!(
+            volume("./conf/../src:/app:ro"),
+            "type: bind\nsource: /project/src\ntarget: /app\nread_only: true\nbind:\n  create_host_path: true\n"
+        );
+        assert_eq!(volume("data:/data"), "type: volume\nsource: data\ntarget: /data\n");
+        assert_eq!(volume("/cache"), "type: volume\ntarget: /cache\n");
+    }
+
+    #[test]
+    fn long_syntax() {
+        let base = Path::new("/project");
+        let volume = |volume: &str| {
+            let volume = normalize_volume(&serde_yaml::from_str(volume).unwrap(), base).unwrap();
+            serde_yaml::to_string(&volume).unwrap()
+        };
+        assert_eq!(
+            volume("{type: tmpfs, target: /tmp, tmpfs: {size: 1000000, mode: 1777}}"),
+            "type: tmpfs\ntarget: /tmp\ntmpfs:\n  size: 1000000\n  mode: 1777\n"
+        );
+        assert_eq!(
+            volume("{type: bind, source: ./src, target: /app, consistency: cached}"),
+            "type: bind\nsource: /project/src\ntarget: /app\nconsistency: cached\n"
+        );
+    }
+
+    #[test]
+    fn merge_override_files() -> Result<(), Box<dyn std::error::Error>> {
+        let project = assert_fs::TempDir::new()?;
+        std::fs::write(project.path().join(".env"), "# Settings\nDB_IMAGE=mariadb:11\nexport PASSWORD=\"secret # not a comment\"\n")?;
+        std::fs::write(project.path().join("compose.yml"), r#"
+services:
+  db:
+    image: ${DB_IMAGE:-mysql}
+    environment:
+      - MYSQL_ROOT_PASSWORD=${PASSWORD}
+      - FROM_SHELL
+      - UNSET
+    volumes:
+      - data:/var/lib/mysql
+      - ./dumps:/dumps
+    ports: ["3306:3306"]
+  debug:
+    image: busybox
+    profiles: [debug]
+volumes:
+  data:
+"#)?;
+        std::fs::write(project.path().join("compose.override.yml"), r#"
+services:
+  db:
+    environment:
+      MYSQL_DATABASE: app
+    volumes:
+      - ./other-dumps:/dumps
+    ports: ["13306:3306"]
+    depends_on: [cache]
+  cache:
+    image: redis
+"#)?;
+
+        let files = [project.path().join("compose.yml"), project.path().join("compose.override.yml")];
+        let environment = [(String::from("FROM_SHELL"), String::from("1"))];
+        let config: Config = serde_yaml::from_value(load(&files, &[], Some("My App"), environment)?)?;
+
+        assert_eq!(config.name, "myapp");
+        assert_eq!(config.services.keys().collect::<Vec<_>>(), vec!["cache", "db"]);
+        let db = &config.services["db"];
+        assert_eq!(db.image.as_deref(), Some("mariadb:11"));
+        let environment = db.environment.clone().unwrap();
+        assert_eq!(environment.get("MYSQL_ROOT_PASSWORD").map(String::as_str), Some("secret # not a comment"));
+        assert_eq!(environment.get("MYSQL_DATABASE").map(String::as_str), Some("app"));
+        assert_eq!(environment.get("FROM_SHELL").map(String::as_str), Some("1"));
+        assert!(!environment.contains_key("UNSET"));
+        let volumes: Vec<(&str, &str)> = db.volumes.iter().flatten().map(|volume| (volume.source.as_str(), volume.target.as_str())).collect();
+        assert_eq!(volumes, vec![("data", "/var/lib/mysql"), (&*format!("{}/other-dumps", project.path().display()), "/dumps")]);
+        let ports: Vec<&str> = db.ports.iter().flatten().map(|port| port.published.as_str()).collect();
+        assert_eq!(ports, vec!["3306", "13306"]);
+        assert_eq!(db.depends_on.as_ref().unwrap()["cache"].condition, "service_started");
+        assert_eq!(config.volumes.unwrap()["data"].name, "myapp_data");
+        assert_eq!(config.networks.unwrap()["default"].name, "myapp_default");
+
+        let config: Config = serde_yaml::from_value(load(&files, &[String::from("debug")], Some("app"), [])?)?;
+        assert!(config.services.contains_key("debug"));
+        Ok(())
+    }
+
+    #[test]
+    fn extends_services() -> Result<(), Box<dyn std::error::Error>> {
+        let project = assert_fs::TempDir::new()?;
+        std::fs::create_dir(project.path().join("common"))?;
+        std::fs::write(project.path().join("common/services.yml"), r#"
+services:
+  php-base:
+    image: php:8.3-fpm
+    environment: [APP_ENV=dev, XDEBUG_MODE=off]
+    volumes: ["./php.ini:/usr/local/etc/php/php.ini"]
+"#)?;
+        std::fs::write(project.path().join("compose.yml"), r#"
+services:
+  php:
+    extends: {file: common/services.yml, service: php-base}
+    environment:
+      XDEBUG_MODE: debug
+  worker:
+    extends: php
+    command: php worker.php
+"#)?;
+
+        let config: Config = serde_yaml::from_value(load(&[project.path().join("compose.yml")], &[], Some("app"), [])?)?;
+        for name in ["php", "worker"] {
+            let service = &config.services[name];
+            assert_eq!(service.image.as_deref(), Some("php:8.3-fpm"));
+            assert!(service.extends.is_none());
+            let environment = service.environment.clone().unwrap();
+            assert_eq!(environment.get("APP_ENV").map(String::as_str), Some("dev"));
+            assert_eq!(environment.get("XDEBUG_MODE").map(String::as_str), Some("debug"));
+            assert_eq!(service.volumes.as_ref().unwrap()[0].source, project.path().join("common/php.ini").display().to_string());
+        }
+        assert!(config.services["worker"].command.is_some());
+
+        std::fs::write(project.path().join("compose.yml"), "services:
+  php:
+    extends: php
+")?;
+        assert!(load(&[project.path().join("compose.yml")], &[], Some("app"), []).unwrap_err().contains("circular reference"));
+        std::fs::write(project.path().join("compose.yml"), "include: [common/services.yml]
+")?;
+        assert!(load(&[project.path().join("compose.yml")], &[], Some("app"), []).unwrap_err().contains("include is not supported"));
+        Ok(())
+    }
+
+    #[test]
+    fn example_project() -> Result<(), Box<dyn std::error::Error>> {
+        let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/php-fpm-and-nginx");
+        let value = load(&[example.join("compose.yml")], &[], None, [(String::from("TLD"), String::from("test"))])?;
+        let config: Config = serde_yaml::from_value(value)?;
+
+        assert_eq!(config.name, "php-fpm-and-nginx");
+        let nginx = &config.services["nginx"];
+        assert_eq!(nginx.labels.as_ref().unwrap()["traefik.http.routers.php-fpm-and-nginx.rule"], "Host(`php-fpm-and-nginx.test`)");
+        assert_eq!(nginx.volumes.as_ref().unwrap()[0].source, example.join("conf/site.conf").display().to_string());
+        assert_eq!(nginx.networks.as_ref().unwrap().keys().collect::<Vec<_>>(), vec!["default", "dev-cli_web"]);
+        assert_eq!(config.resolve_service(&crate::utils::app_config::AppConfig::default(), None), Ok(String::from("php")));
+        assert_eq!(config.networks.unwrap()["dev-cli_web"].name, "dev-cli_web");
+        Ok(())
+    }
+}
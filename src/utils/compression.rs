@@ -0,0 +1,108 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Determines the compression from the extension of a file name.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    pub fn writer<W: Write + 'static>(&self, inner: W) -> std::io::Result<CompressedWriter<W>> {
+        Ok(match self {
+            Compression::None => CompressedWriter::None(inner),
+            Compression::Gzip => CompressedWriter::Gzip(flate2::write::GzEncoder::new(inner, flate2::Compression::default())),
+            Compression::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(inner, 0)?),
+        })
+    }
+
+    pub fn reader<R: Read + 'static>(&self, inner: R) -> std::io::Result<Box<dyn Read>> {
+        Ok(match self {
+            Compression::None => Box::new(inner),
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(inner)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(inner)?),
+        })
+    }
+}
+
+/// Compresses everything written to it, `finish` has to be called at the end
+/// so the compressed stream is completed.
+pub enum CompressedWriter<W: Write> {
+    None(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    pub fn finish(self) -> std::io::Result<W> {
+        match self {
+            CompressedWriter::None(mut inner) => {
+                inner.flush()?;
+                Ok(inner)
+            }
+            CompressedWriter::Gzip(encoder) => encoder.finish(),
+            CompressedWriter::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::None(inner) => inner.write(buf),
+            CompressedWriter::Gzip(encoder) => encoder.write(buf),
+            CompressedWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::None(inner) => inner.flush(),
+            CompressedWriter::Gzip(encoder) => encoder.flush(),
+            CompressedWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let mut writer = compression.writer(Vec::new()).unwrap();
+            writer.write_all(b"CREATE TABLE test (id INT);\n").unwrap();
+            let compressed = writer.finish().unwrap();
+
+            let mut decompressed = String::new();
+            compression.reader(std::io::Cursor::new(compressed)).unwrap().read_to_string(&mut decompressed).unwrap();
+            assert_eq!(decompressed, "CREATE TABLE test (id INT);\n");
+        }
+    }
+
+    #[test]
+    fn from_path() {
+        assert_eq!(Compression::from_path(Path::new("dump.sql.gz")), Compression::Gzip);
+        assert_eq!(Compression::from_path(Path::new("dump.sql.zst")), Compression::Zstd);
+        assert_eq!(Compression::from_path(Path::new("dump.sql")), Compression::None);
+    }
+}
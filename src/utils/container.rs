@@ -0,0 +1,235 @@
+use bollard::Docker;
+use bollard::container::{ListContainersOptions, LogOutput, LogsOptions};
+use bollard::exec::{CreateExecOptions, ResizeExecOptions, StartExecOptions, StartExecResults};
+use bollard::models::ContainerSummary;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read, Write};
+use tokio::io::AsyncWriteExt;
+
+// Labels docker compose puts on every container it creates
+pub const LABEL_PROJECT: &str = "com.docker.compose.project";
+pub const LABEL_SERVICE: &str = "com.docker.compose.service";
+pub const LABEL_ONEOFF: &str = "com.docker.compose.oneoff";
+
+/// Lists all containers (running or not) of a compose project, optionally
+/// limited to a single service. Containers of `docker compose run` are left out.
+pub async fn project_containers(
+    docker: &Docker,
+    project: &str,
+    service: Option<&str>,
+) -> Result<Vec<ContainerSummary>, bollard::errors::Error> {
+    let mut labels = vec![
+        format!("{}={}", LABEL_PROJECT, project),
+        format!("{}=False", LABEL_ONEOFF),
+    ];
+    if let Some(service) = service {
+        labels.push(format!("{}={}", LABEL_SERVICE, service));
+    }
+    let mut filters = HashMap::new();
+    filters.insert(String::from("label"), labels);
+
+    docker.list_containers(Some(ListContainersOptions {
+        all: true,
+        filters,
+        ..Default::default()
+    })).await
+}
+
+/// Returns the last lines a container wrote to stdout and stderr.
+pub async fn tail_logs(
+    docker: &Docker,
+    container: &str,
+    lines: usize,
+) -> Result<Vec<String>, bollard::errors::Error> {
+    let mut stream = docker.logs(container, Some(LogsOptions::<String> {
+        stdout: true,
+        stderr: true,
+        tail: lines.to_string(),
+        ..Default::default()
+    }));
+
+    let mut output = String::new();
+    while let Some(log) = stream.next().await {
+        output.push_str(&log?.to_string());
+    }
+
+    Ok(output.lines().map(String::from).collect())
+}
+
+/// Runs a command in a running container, connected to the terminal of
+/// dev-cli, and returns its exit code. A TTY is only allocated when stdin and
+/// stdout are terminals, so the output can also be piped or redirected.
+pub async fn exec(
+    docker: &Docker,
+    container: &str,
+    command: Vec<String>,
+    user: Option<&str>,
+    workdir: Option<&str>,
+    env: &[(String, String)],
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    let exec = docker.create_exec(container, CreateExecOptions {
+        attach_stdin: Some(true),
+        attach_stdout: Some(true),
+        attach_stderr: Some(true),
+        tty: Some(tty),
+        cmd: Some(command),
+        user: user.map(String::from),
+        working_dir: workdir.map(String::from),
+        env: Some(env.iter().map(|(key, value)| format!("{}={}", key, value)).collect()),
+        ..Default::default()
+    }).await?;
+    let (mut output, mut input) = match docker.start_exec(&exec.id, None).await? {
+        StartExecResults::Attached { output, input } => (output, input),
+        StartExecResults::Detached => return Err("The command was started detached".into()),
+    };
+
+    // Keys like Ctrl-C are passed on to the container as they are typed
+    let raw_mode = if tty { Some(RawMode::enable()?) } else { None };
+    if tty {
+        resize(docker, &exec.id).await;
+    }
+
+    // Reading stdin blocks, so it's done on its own thread. The channel closes
+    // at the end of the input.
+    let (sender, mut stdin) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+    std::thread::spawn(move || {
+        let mut buffer = [0; 8192];
+        loop {
+            match std::io::stdin().read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if sender.blocking_send(buffer[..read].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let mut window_changes = WindowChanges::new()?;
+    // Created once, so a Ctrl-C arriving while another branch runs isn't lost
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut interrupted = false;
+    let mut stdin_open = true;
+    loop {
+        tokio::select! {
+            chunk = output.next() => match chunk {
+                Some(chunk) => {
+                    match chunk? {
+                        LogOutput::StdErr { message } => {
+                            std::io::stderr().write_all(&message)?;
+                            std::io::stderr().flush()?;
+                        }
+                        LogOutput::StdOut { message } | LogOutput::Console { message } => {
+                            std::io::stdout().write_all(&message)?;
+                            std::io::stdout().flush()?;
+                        }
+                        LogOutput::StdIn { .. } => {}
+                    }
+                }
+                None => break,
+            },
+            data = stdin.recv(), if stdin_open => match data {
+                // The command may have stopped reading, its exit code tells what happened
+                Some(data) => {
+                    let _ = input.write_all(&data).await;
+                }
+                None => {
+                    // Closing our side lets the command know the input is complete
+                    stdin_open = false;
+                    let _ = input.shutdown().await;
+                }
+            },
+            _ = window_changes.recv(), if tty => resize(docker, &exec.id).await,
+            // With a TTY, Ctrl-C reaches the command as a key. Without one, the
+            // signal is passed on and we wait for the command to exit.
+            _ = &mut ctrl_c, if !tty && !interrupted => {
+                interrupted = true;
+                if let Err(error) = interrupt(docker, container, &exec.id).await {
+                    eprintln!("Could not pass Ctrl-C on, the command keeps running in the container ({})", error);
+                    return Ok(130);
+                }
+            }
+        }
+    }
+    drop(raw_mode);
+
+    Ok(docker.inspect_exec(&exec.id).await?.exit_code.unwrap_or_default())
+}
+
+/// Sends SIGINT to the process of an exec. Docker only knows its PID on the
+/// host, which is translated to the PID in the container through /proc, so
+/// this only works when the Docker daemon runs on the same (Linux) machine.
+async fn interrupt(docker: &Docker, container: &str, exec: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let pid = docker.inspect_exec(exec).await?.pid.ok_or("the command has no process")?;
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid))?;
+    let container_pid = status
+        .lines()
+        .find_map(|line| line.strip_prefix("NSpid:"))
+        .and_then(|pids| pids.split_whitespace().last())
+        .ok_or("the PID in the container is unknown")?;
+
+    let kill = docker.create_exec(container, CreateExecOptions {
+        cmd: Some(vec!["kill", "-INT", container_pid]),
+        user: Some("root"),
+        ..Default::default()
+    }).await?;
+    docker.start_exec(&kill.id, Some(StartExecOptions { detach: true, ..Default::default() })).await?;
+    Ok(())
+}
+
+/// Resolves whenever the terminal is resized. Windows has no signal for it,
+/// so the size is only set once there.
+#[cfg(unix)]
+struct WindowChanges(tokio::signal::unix::Signal);
+
+#[cfg(unix)]
+impl WindowChanges {
+    fn new() -> std::io::Result<Self> {
+        Ok(WindowChanges(tokio::signal::unix::signal(tokio::signal::unix::SignalKind::window_change())?))
+    }
+
+    async fn recv(&mut self) {
+        self.0.recv().await;
+    }
+}
+
+#[cfg(not(unix))]
+struct WindowChanges;
+
+#[cfg(not(unix))]
+impl WindowChanges {
+    fn new() -> std::io::Result<Self> {
+        Ok(WindowChanges)
+    }
+
+    async fn recv(&mut self) {
+        std::future::pending::<()>().await
+    }
+}
+
+/// Sets the TTY of an exec to the size of the terminal.
+async fn resize(docker: &Docker, exec: &str) {
+    if let Ok((width, height)) = crossterm::terminal::size() {
+        let _ = docker.resize_exec(exec, ResizeExecOptions { height, width }).await;
+    }
+}
+
+/// Puts the terminal into raw mode until dropped.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> std::io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
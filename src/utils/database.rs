@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use crate::utils::app_config::AppConfig;
+use crate::utils::docker_compose::{Config, DockerCompose};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    Mysql,
+    Mariadb,
+    Postgres,
+}
+
+impl Engine {
+    /// Detects the engine from the name of the image, e.g. "mariadb:11" or
+    /// "registry.example.com/postgres:16-alpine".
+    pub fn detect(image: &str) -> Option<Self> {
+        let name = image.rsplit('/').next().unwrap_or(image);
+        let name = name.split([':', '@']).next().unwrap_or(name).to_lowercase();
+        if name.contains("mariadb") {
+            Some(Engine::Mariadb)
+        } else if name.contains("mysql") || name.contains("percona") {
+            Some(Engine::Mysql)
+        } else if name.contains("postgres") || name.contains("postgis") || name.contains("timescaledb") {
+            Some(Engine::Postgres)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Engine::Mysql => write!(f, "MySQL"),
+            Engine::Mariadb => write!(f, "MariaDB"),
+            Engine::Postgres => write!(f, "PostgreSQL"),
+        }
+    }
+}
+
+/// The database service of a project, as configured with `database_container`
+#[derive(Debug)]
+pub struct Database {
+    pub service: String,
+    pub engine: Engine,
+    pub image: String,
+    environment: BTreeMap<String, String>,
+}
+
+impl Database {
+    pub fn from_config(config: &Config, app_config: &AppConfig) -> Result<Self, String> {
+        let service_name = app_config.database_container.clone().unwrap_or(String::from("db"));
+        let service = config.services.get(&service_name).ok_or(format!(
+            "The database service '{}' does not exist, set 'database_container' in your config file",
+            service_name
+        ))?;
+        let image = service.image.clone().ok_or(format!(
+            "The database service '{}' has no image to detect the database engine from",
+            service_name
+        ))?;
+        let engine = Engine::detect(&image).ok_or(format!(
+            "Could not detect the database engine of the image '{}', only MySQL, MariaDB and PostgreSQL are supported",
+            image
+        ))?;
+
+        Ok(Database {
+            service: service_name,
+            engine,
+            image,
+            environment: service.environment.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Looks up a variable of the official images, MariaDB also understands
+    /// the MySQL variables.
+    fn variable(&self, name: &str) -> Option<&str> {
+        let mariadb_name = name.replace("MYSQL_", "MARIADB_");
+        let value = match self.engine {
+            Engine::Mariadb => self.environment.get(&mariadb_name).or(self.environment.get(name)),
+            _ => self.environment.get(name),
+        };
+        value.map(String::as_str).filter(|value| !value.is_empty())
+    }
+
+    /// The database the application uses
+    pub fn name(&self) -> Option<String> {
+        match self.engine {
+            Engine::Mysql | Engine::Mariadb => self.variable("MYSQL_DATABASE").map(String::from),
+            Engine::Postgres => self.variable("POSTGRES_DB")
+                .or(self.variable("POSTGRES_USER"))
+                .or(Some("postgres"))
+                .map(String::from),
+        }
+    }
+
+    /// The user and password to connect with. For MySQL and MariaDB the root
+    /// user is preferred, as it can also dump routines and triggers.
+    fn credentials(&self) -> (String, Option<String>) {
+        match self.engine {
+            Engine::Mysql | Engine::Mariadb => {
+                if let Some(password) = self.variable("MYSQL_ROOT_PASSWORD") {
+                    (String::from("root"), Some(password.to_string()))
+                } else if let (Some(user), password) = (self.variable("MYSQL_USER"), self.variable("MYSQL_PASSWORD")) {
+                    (user.to_string(), password.map(String::from))
+                } else {
+                    (String::from("root"), None)
+                }
+            }
+            Engine::Postgres => (
+                self.variable("POSTGRES_USER").unwrap_or("postgres").to_string(),
+                self.variable("POSTGRES_PASSWORD").map(String::from),
+            ),
+        }
+    }
+
+    /// Environment variables passing the password to the client tools
+    pub fn environment(&self) -> Vec<(String, String)> {
+        let variable = match self.engine {
+            Engine::Mysql | Engine::Mariadb => "MYSQL_PWD",
+            Engine::Postgres => "PGPASSWORD",
+        };
+        self.credentials().1
+            .map(|password| vec![(String::from(variable), password)])
+            .unwrap_or_default()
+    }
+
+    /// Builds a command running one of the client tools. Newer MariaDB images
+    /// only ship the tools under their new name (e.g. `mariadb-dump`), so for
+    /// MySQL and MariaDB whichever exists is used.
+    fn tool(&self, mysql_tool: &str, mariadb_tool: &str, arguments: Vec<String>) -> Vec<String> {
+        let mut command = match self.engine {
+            Engine::Mysql | Engine::Mariadb => vec![
+                String::from("sh"),
+                String::from("-c"),
+                format!(
+                    "if command -v {1} >/dev/null 2>&1; then exec {1} \"$@\"; else exec {0} \"$@\"; fi",
+                    mysql_tool, mariadb_tool
+                ),
+                String::from("sh"),
+            ],
+            Engine::Postgres => vec![String::from(mysql_tool)],
+        };
+        command.extend(arguments);
+        command
+    }
+
+    /// The command writing a SQL dump of the database to stdout
+    pub fn dump_command(&self) -> Vec<String> {
+        let (user, _) = self.credentials();
+        match self.engine {
+            Engine::Mysql | Engine::Mariadb => {
+                let mut arguments = vec![
+                    format!("--user={}", user),
+                    String::from("--single-transaction"),
+                    String::from("--routines"),
+                    String::from("--triggers"),
+                ];
+                match self.name() {
+                    Some(name) => arguments.push(name),
+                    None => arguments.push(String::from("--all-databases")),
+                }
+                self.tool("mysqldump", "mariadb-dump", arguments)
+            }
+            Engine::Postgres => self.tool("pg_dump", "pg_dump", vec![
+                format!("--username={}", user),
+                String::from("--no-owner"),
+                self.name().unwrap(),
+            ]),
+        }
+    }
+
+    /// The command printing the version of the database server
+    pub fn version_command(&self) -> Vec<String> {
+        match self.engine {
+            Engine::Mysql | Engine::Mariadb => self.tool("mysqld", "mariadbd", vec![String::from("--version")]),
+            Engine::Postgres => self.tool("postgres", "postgres", vec![String::from("--version")]),
+        }
+    }
+
+    /// The command executing the SQL read from stdin in the database
+    pub fn import_command(&self) -> Vec<String> {
+        let (user, _) = self.credentials();
+        match self.engine {
+            Engine::Mysql | Engine::Mariadb => {
+                let mut arguments = vec![format!("--user={}", user)];
+                arguments.extend(self.name());
+                self.tool("mysql", "mariadb", arguments)
+            }
+            Engine::Postgres => self.tool("psql", "psql", vec![
+                format!("--username={}", user),
+                String::from("--quiet"),
+                String::from("--set=ON_ERROR_STOP=1"),
+                format!("--dbname={}", self.name().unwrap()),
+            ]),
+        }
+    }
+
+    /// Asks the database server for its version, falling back to the image tag.
+    pub fn server_version(&self, docker_compose: &DockerCompose) -> Option<String> {
+        docker_compose
+            .exec_command(&self.service, None, None, &[], false)
+            .args(&self.version_command())
+            .stderr(subprocess::NullFile)
+            .capture()
+            .ok()
+            .filter(|capture| capture.success())
+            .and_then(|capture| parse_version(&capture.stdout_str()))
+            .or(image_version(&self.image))
+    }
+
+    /// The command dropping the database and creating it again empty. Open
+    /// connections to a PostgreSQL database prevent dropping it, they are
+    /// closed with `WITH (FORCE)` from version 13 on and terminated before
+    /// the drop on older (or unknown) versions.
+    pub fn recreate_command(&self, server_version: Option<&str>) -> Option<Vec<String>> {
+        let (user, _) = self.credentials();
+        let name = self.name()?;
+        Some(match self.engine {
+            Engine::Mysql | Engine::Mariadb => self.tool("mysql", "mariadb", vec![
+                format!("--user={}", user),
+                format!("--execute=DROP DATABASE IF EXISTS `{0}`; CREATE DATABASE `{0}`;", name),
+            ]),
+            Engine::Postgres => {
+                let force = server_version
+                    .and_then(|version| major_version(version).parse::<u32>().ok())
+                    .is_some_and(|major| major >= 13);
+                let mut arguments = vec![
+                    format!("--username={}", user),
+                    String::from("--quiet"),
+                    String::from("--output=/dev/null"),
+                    String::from("--dbname=postgres"),
+                ];
+                // Every command runs in its own transaction, as required for these
+                if force {
+                    arguments.push(format!("--command=DROP DATABASE IF EXISTS \"{}\" WITH (FORCE)", name));
+                } else {
+                    arguments.push(format!(
+                        "--command=SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = '{}' AND pid <> pg_backend_pid()",
+                        name.replace('\'', "''")
+                    ));
+                    arguments.push(format!("--command=DROP DATABASE IF EXISTS \"{}\"", name));
+                }
+                arguments.push(format!("--command=CREATE DATABASE \"{}\"", name));
+                self.tool("psql", "psql", arguments)
+            }
+        })
+    }
+}
+
+/// Extracts the first version number from the output of a `--version` call,
+/// e.g. "16.1" from "postgres (PostgreSQL) 16.1 (Debian 16.1-1.pgdg120+1)".
+pub fn parse_version(output: &str) -> Option<String> {
+    output
+        .split(|char: char| char.is_whitespace() || char == '-' || char == '+')
+        .find(|word| word.contains('.') && word.split('.').all(|part| !part.is_empty() && part.chars().all(|char| char.is_ascii_digit())))
+        .map(String::from)
+}
+
+/// Takes the version from the tag of an image, e.g. "16" from "postgres:16-alpine".
+pub fn image_version(image: &str) -> Option<String> {
+    let tag = image.rsplit('/').next()?.split_once(':')?.1;
+    let version: String = tag.chars().take_while(|char| char.is_ascii_digit() || *char == '.').collect();
+    Some(version.trim_end_matches('.').to_string()).filter(|version| !version.is_empty())
+}
+
+/// The major version, which has to match for a dump to be compatible
+pub fn major_version(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(image: &str, environment: &[(&str, &str)]) -> Database {
+        Database {
+            service: String::from("db"),
+            engine: Engine::detect(image).unwrap(),
+            image: image.to_string(),
+            environment: environment.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn detect_engine_from_image() {
+        assert_eq!(Engine::detect("mysql:8.0"), Some(Engine::Mysql));
+        assert_eq!(Engine::detect("mariadb"), Some(Engine::Mariadb));
+        assert_eq!(Engine::detect("docker.io/library/postgres:16-alpine"), Some(Engine::Postgres));
+        assert_eq!(Engine::detect("postgis/postgis:16-3.4"), Some(Engine::Postgres));
+        assert_eq!(Engine::detect("nginx"), None);
+    }
+
+    #[test]
+    fn credentials_from_environment() {
+        let mysql = database("mysql:8.0", &[("MYSQL_ROOT_PASSWORD", "secret"), ("MYSQL_DATABASE", "app")]);
+        assert_eq!(mysql.name(), Some(String::from("app")));
+        assert_eq!(mysql.environment(), vec![(String::from("MYSQL_PWD"), String::from("secret"))]);
+        assert!(mysql.dump_command().contains(&String::from("--user=root")));
+
+        let mariadb = database("mariadb:11", &[("MARIADB_USER", "app"), ("MARIADB_PASSWORD", "password")]);
+        assert_eq!(mariadb.credentials(), (String::from("app"), Some(String::from("password"))));
+        assert!(mariadb.dump_command().contains(&String::from("--all-databases")));
+
+        let postgres = database("postgres:16", &[("POSTGRES_USER", "app"), ("POSTGRES_PASSWORD", "password")]);
+        assert_eq!(postgres.name(), Some(String::from("app")));
+        assert_eq!(postgres.dump_command(), vec!["pg_dump", "--username=app", "--no-owner", "app"]);
+    }
+
+    #[test]
+    fn recreate_depending_on_version() {
+        let postgres = database("postgres:12", &[("POSTGRES_DB", "app")]);
+        let drop_statements = |version| -> Vec<String> {
+            postgres.recreate_command(version).unwrap().into_iter().filter(|argument| argument.starts_with("--command=")).collect()
+        };
+        assert_eq!(drop_statements(Some("16.1")), vec![
+            "--command=DROP DATABASE IF EXISTS \"app\" WITH (FORCE)",
+            "--command=CREATE DATABASE \"app\"",
+        ]);
+        for version in [Some("12.17"), None] {
+            assert_eq!(drop_statements(version), vec![
+                "--command=SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = 'app' AND pid <> pg_backend_pid()",
+                "--command=DROP DATABASE IF EXISTS \"app\"",
+                "--command=CREATE DATABASE \"app\"",
+            ]);
+        }
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(parse_version("mysqld  Ver 8.0.36 for Linux on x86_64 (MySQL Community Server - GPL)"), Some(String::from("8.0.36")));
+        assert_eq!(parse_version("mariadbd  Ver 11.2.2-MariaDB-1:11.2.2+maria~ubu2204 for debian-linux-gnu"), Some(String::from("11.2.2")));
+        assert_eq!(parse_version("postgres (PostgreSQL) 16.1 (Debian 16.1-1.pgdg120+1)"), Some(String::from("16.1")));
+        assert_eq!(image_version("postgres:16-alpine"), Some(String::from("16")));
+        assert_eq!(image_version("registry:5000/mysql:8.0"), Some(String::from("8.0")));
+        assert_eq!(image_version("mariadb:latest"), None);
+        assert_eq!(image_version("mariadb"), None);
+        assert_eq!(major_version("11.2.2"), "11");
+    }
+}
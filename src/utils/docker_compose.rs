@@ -1,164 +1,1123 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::Value;
+use crate::utils::app_config::AppConfig;
+use crate::utils::compose_loader;
+use crate::utils::suggest::did_you_mean;
+
+/// The compose files docker compose looks for, in the order it prefers them
+const DEFAULT_FILES: [&str; 4] = ["compose.yaml", "compose.yml", "docker-compose.yaml", "docker-compose.yml"];
+
 #[derive(Debug)]
 pub struct DockerCompose {
-    file: std::path::PathBuf,
+    project_dir: PathBuf,
+    files: Vec<PathBuf>,
+    profiles: Vec<String>,
+    project_name: Option<String>,
 }
 
 impl DockerCompose {
-    pub fn new(file: std::path::PathBuf) -> Self {
+    pub fn new(project_dir: PathBuf, files: Vec<PathBuf>, profiles: Vec<String>, project_name: Option<String>) -> Self {
         Self {
-            file,
+            project_dir,
+            files,
+            profiles,
+            project_name,
         }
     }
 
-    pub fn config(&self) -> Result<Config, serde_yaml::Error> {
-        let output_cmd = if cfg!(target_os = "windows") {
-            panic!("Windows is not supported yet")
-            //std::process::Command::new("cmd")
-            //    .args(["/C", "echo hello"])
-            //    .output()
-        } else {
-            std::process::Command::new("sh")
-                .arg("-c")
-                .arg("docker compose config")
-                .current_dir(&self.file.parent().unwrap())
-                .output()
-        };
-        let output = match output_cmd {
-            Ok(output) => output,
-            Err(error) => {
-                println!("Docker doesn't seem to be turned on ({})", error);
-                sysexits::ExitCode::OsErr.exit()
-            },
-        };
-        let config_string = match std::str::from_utf8(&output.stdout) {
-            Ok(stdout) => stdout,
-            Err(error) => {
-                println!("Error: {}", error);
-                sysexits::ExitCode::OsErr.exit()
-            },
+    /// Uses the compose files, profiles and project name set in the config.
+    /// Without configured files, the files docker compose would pick by
+    /// default are discovered in the project root.
+    pub fn from_config(project_root: &Path, app_config: &AppConfig) -> Result<Self, String> {
+        let files = match &app_config.compose_files {
+            Some(files) if !files.is_empty() => {
+                let files: Vec<PathBuf> = files.iter().map(|file| project_root.join(file)).collect();
+                if let Some(missing) = files.iter().find(|file| !file.is_file()) {
+                    return Err(format!("The compose file {} set in 'compose_files' does not exist", missing.display()));
+                }
+                files
+            }
+            _ => Self::discover(project_root).ok_or(format!(
+                "Could not find a docker compose file in the project root ({}), add one of {} or set 'compose_files' in your config file",
+                project_root.display(),
+                DEFAULT_FILES.join(", ")
+            ))?,
         };
-        let config = serde_yaml::from_str::<Config>(config_string);
-        config
+
+        Ok(Self::new(
+            project_root.to_path_buf(),
+            files,
+            app_config.compose_profiles.clone().unwrap_or_default(),
+            app_config.compose_project_name.clone(),
+        ))
     }
 
-    pub fn exec(&self, service: Option<String>, user: Option<String>, command: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-        let service_to_exec = match service {
-            Some(service) => service,
-            None => {
-                let config = self.config().unwrap();
-                let first_service = config.services.keys().next().unwrap();
-                first_service.to_string()
-            },
-        };
+    /// Finds the compose file docker compose uses by default, together with
+    /// its override file (e.g. `compose.override.yaml`) if there is one.
+    fn discover(project_root: &Path) -> Option<Vec<PathBuf>> {
+        let file = DEFAULT_FILES.iter().map(|file| project_root.join(file)).find(|file| file.is_file())?;
+        let stem = file.file_stem()?.to_string_lossy().to_string();
+        let override_file = ["yaml", "yml"]
+            .iter()
+            .map(|extension| project_root.join(format!("{}.override.{}", stem, extension)))
+            .find(|file| file.is_file());
+
+        let mut files = vec![file];
+        files.extend(override_file);
+        Some(files)
+    }
+
+    /// Builds a `docker compose` invocation running in the project directory,
+    /// with the compose files, profiles and project name of the project.
+    fn command(&self) -> subprocess::Exec {
+        let mut cmd = subprocess::Exec::cmd("docker")
+            .arg("compose")
+            .cwd(&self.project_dir);
+        for file in &self.files {
+            cmd = cmd.arg("--file").arg(file);
+        }
+        for profile in &self.profiles {
+            cmd = cmd.arg("--profile").arg(profile);
+        }
+        if let Some(project_name) = &self.project_name {
+            cmd = cmd.arg("--project-name").arg(project_name);
+        }
+        cmd
+    }
 
+    fn run(&self, cmd: subprocess::Exec) -> Result<(), Box<dyn std::error::Error>> {
         if cfg!(target_os = "windows") {
             panic!("Windows is not supported yet")
-            //std::process::Command::new("cmd")
-            //    .args(["/C", "echo hello"])
-            //    .output()
-        } else {
-            let mut cmd = subprocess::Exec::cmd("docker").arg("compose").arg("exec");
-            cmd = match user {
-                Some(user) => cmd
-                    .arg("--user").arg(user),
-                None => cmd,
-            };
-            cmd
-                .arg(service_to_exec)
-                .args(&command)
-                .cwd(&self.file.parent().unwrap())
-                .join()?
-        };
+        }
+        let description = cmd.to_cmdline_lossy();
+        let status = cmd.join()?;
+        if !status.success() {
+            return Err(format!("`{}` failed ({:?})", description, status).into());
+        }
         Ok(())
     }
+
+    /// Stops the containers of any compose project by its name, without
+    /// needing its compose file.
+    pub fn stop_project(project: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let cmd = subprocess::Exec::cmd("docker")
+            .arg("compose")
+            .arg("--project-name").arg(project)
+            .arg("stop");
+        let status = cmd.join()?;
+        if !status.success() {
+            return Err(format!("Could not stop the project '{}' ({:?})", project, status).into());
+        }
+        Ok(())
+    }
+
+    /// Creates and starts the containers of the given services in the
+    /// background, or of the whole project if no service is given.
+    pub fn up(&self, services: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        self.run(self.command().arg("up").arg("--detach").args(services))
+    }
+
+    /// Stops and removes the containers and networks of the project.
+    pub fn down(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.run(self.command().arg("down"))
+    }
+
+    /// Stops and removes the containers of the given services.
+    pub fn remove(&self, services: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        self.run(self.command().arg("rm").arg("--stop").arg("--force").args(services))
+    }
+
+    /// Builds a `docker compose exec` for a service, the program to run has
+    /// to be appended by the caller. Without a TTY the output can be captured.
+    pub fn exec_command(&self, service: &str, user: Option<&str>, workdir: Option<&str>, env: &[(String, String)], tty: bool) -> subprocess::Exec {
+        self.container_command(&["exec"], service, user, workdir, env, tty)
+    }
+
+    /// Builds a `docker compose run` starting a new container of a service,
+    /// which is removed once the command exits.
+    pub fn run_command(&self, service: &str, user: Option<&str>, workdir: Option<&str>, env: &[(String, String)], tty: bool) -> subprocess::Exec {
+        self.container_command(&["run", "--rm"], service, user, workdir, env, tty)
+    }
+
+    fn container_command(&self, subcommand: &[&str], service: &str, user: Option<&str>, workdir: Option<&str>, env: &[(String, String)], tty: bool) -> subprocess::Exec {
+        let mut cmd = self.command().args(subcommand);
+        if !tty {
+            cmd = cmd.arg("--no-TTY");
+        }
+        // Only the names are passed as arguments, docker compose takes the
+        // values from its environment. This keeps passwords out of the
+        // process list.
+        for (key, value) in env {
+            cmd = cmd.arg("--env").arg(key).env(key, value);
+        }
+        if let Some(user) = user {
+            cmd = cmd.arg("--user").arg(user);
+        }
+        if let Some(workdir) = workdir {
+            cmd = cmd.arg("--workdir").arg(workdir);
+        }
+        cmd.arg(service)
+    }
+
+    /// Builds a `docker compose exec` running a shell command in a service.
+    pub fn exec_shell_command(&self, service: &str, user: Option<&str>, workdir: Option<&str>, command: &str, tty: bool) -> subprocess::Exec {
+        self.exec_command(service, user, workdir, &[], tty).arg("sh").arg("-c").arg(command)
+    }
+
+    /// Whether a program can be executed in the container of a service.
+    pub fn has_program(&self, service: &str, program: &str) -> bool {
+        self.exec_command(service, None, None, &[], false)
+            .arg(program).arg("-c").arg("exit 0")
+            .stdout(subprocess::NullFile)
+            .stderr(subprocess::NullFile)
+            .join()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    /// Reads the compose config natively, falling back to `docker compose
+    /// config` for anything the native loader doesn't understand.
+    pub fn config(&self) -> Result<Config, Box<dyn std::error::Error>> {
+        let native_error = match self.native_config().and_then(|value| serde_yaml::from_value(value).map_err(|error| error.to_string())) {
+            Ok(config) => return Ok(config),
+            Err(error) => error,
+        };
+        match self.cli_config().and_then(|value| Ok(serde_yaml::from_value(value)?)) {
+            Ok(config) => Ok(config),
+            Err(error) => Err(format!("{} (reading the files directly failed as well: {})", error, native_error).into()),
+        }
+    }
+
+    /// The compose config as read by dev-cli itself
+    pub fn native_config(&self) -> Result<serde_yaml::Value, String> {
+        compose_loader::load(&self.files, &self.profiles, self.project_name.as_deref(), std::env::vars())
+    }
+
+    /// The compose config as printed by `docker compose config`
+    pub fn cli_config(&self) -> Result<serde_yaml::Value, Box<dyn std::error::Error>> {
+        if cfg!(target_os = "windows") {
+            panic!("Windows is not supported yet")
+        }
+        let output = self.command()
+            .arg("config")
+            .stdout(subprocess::Redirection::Pipe)
+            .stderr(subprocess::Redirection::Pipe)
+            .capture()
+            .map_err(|error| format!("Could not run docker compose ({})", error))?;
+        if !output.success() {
+            return Err(output.stderr_str().trim().into());
+        }
+        Ok(serde_yaml::from_slice(&output.stdout)?)
+    }
 }
 
+/// Label marking the service commands run in when no service is given
+const DEFAULT_SERVICE_LABEL: &str = "dev-cli.default";
+
+/// A compose project. Short syntax is accepted everywhere and normalised to
+/// the long syntax, so serializing it gives the output of `docker compose config`.
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Config {
-    name: String,
-    services: std::collections::BTreeMap<String, Service>,
-    networks: Option<std::collections::BTreeMap<String, Network>>,
-    volumes: Option<std::collections::BTreeMap<String, Volume>>,
-    secrets: Option<std::collections::BTreeMap<String, Secret>>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default)]
+    pub services: BTreeMap<String, Service>,
+    #[serde(default, deserialize_with = "definitions", skip_serializing_if = "Option::is_none")]
+    pub networks: Option<BTreeMap<String, Network>>,
+    #[serde(default, deserialize_with = "definitions", skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<BTreeMap<String, Volume>>,
+    #[serde(default, deserialize_with = "definitions", skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<BTreeMap<String, Secret>>,
+}
+
+impl Config {
+    /// Resolves the service a command runs in. A given name may be an alias
+    /// from the config. Without one, the configured `default_service` is used,
+    /// else the service labeled `dev-cli.default: true`, else the first one.
+    pub fn resolve_service(&self, app_config: &AppConfig, name: Option<&str>) -> Result<String, String> {
+        let name = match name.or(app_config.default_service.as_deref()) {
+            Some(name) => name,
+            None => {
+                let labeled = self.services.iter().find(|(_, service)| {
+                    service.labels.as_ref().and_then(|labels| labels.get(DEFAULT_SERVICE_LABEL)).is_some_and(|value| value == "true")
+                });
+                return labeled
+                    .or_else(|| self.services.iter().next())
+                    .map(|(name, _)| name.clone())
+                    .ok_or(String::from("The project has no services"));
+            }
+        };
+
+        if self.services.contains_key(name) {
+            return Ok(name.to_string());
+        }
+        let aliases = app_config.service_aliases.clone().unwrap_or_default();
+        if let Some(service) = aliases.get(name) {
+            return match self.services.contains_key(service) {
+                true => Ok(service.clone()),
+                false => Err(format!("The alias '{}' points to the service '{}', which does not exist", name, service)),
+            };
+        }
+
+        let candidates: Vec<&str> = self.services.keys().chain(aliases.keys()).map(String::as_str).collect();
+        match did_you_mean(name, &candidates) {
+            Some(suggestion) => Err(format!("The service '{}' does not exist, did you mean '{}'?", name, suggestion)),
+            None => Err(format!("The service '{}' does not exist", name)),
+        }
+    }
+
+    /// The directory in the container of a service matching the current
+    /// directory on the host. If it isn't mounted into the container, the
+    /// given default is used, or otherwise the default working directory of
+    /// the container, which is pointed out with a notice.
+    pub fn workdir(&self, service: &str, default: Option<&str>) -> Option<String> {
+        let cwd = std::env::current_dir().ok()?;
+        let workdir = self.services.get(service)?.container_path(&cwd);
+        if workdir.is_some() || default.is_some() {
+            return workdir.or(default.map(String::from));
+        }
+        eprintln!("{} is not mounted into '{}', using its default working directory", cwd.display(), service);
+        None
+    }
+
+    /// Returns the names of the services in the order they have to be started
+    /// in, so that every service comes after the services it depends on.
+    /// Services without a dependency between them are ordered alphabetically.
+    pub fn startup_order(&self) -> Vec<String> {
+        let mut order: Vec<String> = Vec::new();
+        let mut remaining: BTreeSet<&String> = self.services.keys().collect();
+
+        while !remaining.is_empty() {
+            let next = remaining
+                .iter()
+                .find(|service| {
+                    self.dependencies(service)
+                        .iter()
+                        .all(|(dependency, _)| !remaining.contains(dependency))
+                })
+                // A dependency cycle can't be resolved, docker compose will
+                // complain about it anyway, so just continue alphabetically
+                .or_else(|| remaining.iter().next())
+                .copied()
+                .unwrap();
+            remaining.remove(next);
+            order.push(next.to_string());
+        }
+
+        order
+    }
+
+    /// Returns the services the given service depends on, together with the
+    /// condition they have to fulfill.
+    pub fn dependencies(&self, service: &str) -> Vec<(&String, &ServiceDependsOn)> {
+        self.services
+            .get(service)
+            .and_then(|service| service.depends_on.as_ref())
+            .map(|depends_on| depends_on.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the given services together with all services that directly or
+    /// indirectly depend on them, in startup order.
+    pub fn with_dependents(&self, services: &[String]) -> Vec<String> {
+        let order = self.startup_order();
+        let mut affected: BTreeSet<&str> = services.iter().map(String::as_str).collect();
+        // A single pass is enough, as dependents always come after the
+        // services they depend on
+        for service in &order {
+            let depends_on_affected = self.dependencies(service)
+                .iter()
+                .any(|(dependency, _)| affected.contains(dependency.as_str()));
+            if depends_on_affected {
+                affected.insert(service);
+            }
+        }
+
+        order
+            .iter()
+            .filter(|service| affected.contains(service.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the given services together with all services they directly or
+    /// indirectly depend on, in startup order.
+    pub fn with_dependencies(&self, services: &[String]) -> Vec<String> {
+        let order = self.startup_order();
+        let mut needed: BTreeSet<&str> = services.iter().map(String::as_str).collect();
+        // Dependencies always come before the services depending on them, so
+        // a single pass backwards is enough
+        for service in order.iter().rev() {
+            if needed.contains(service.as_str()) {
+                needed.extend(self.dependencies(service).iter().map(|(dependency, _)| dependency.as_str()));
+            }
+        }
+
+        order
+            .iter()
+            .filter(|service| needed.contains(service.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the named volumes the project owns. External volumes are
+    /// managed outside of the project and therefore never part of it.
+    pub fn project_volumes(&self) -> Vec<&Volume> {
+        self.volumes
+            .iter()
+            .flat_map(|volumes| volumes.values())
+            .filter(|volume| volume.external != Some(true))
+            .collect()
+    }
+
+    /// Whether another service waits for the given service to exit
+    /// successfully, meaning it is expected to stop after doing its job.
+    pub fn runs_to_completion(&self, service: &str) -> bool {
+        self.services.keys().any(|other| {
+            self.dependencies(other).iter().any(|(dependency, depends_on)| {
+                dependency.as_str() == service
+                    && depends_on.condition == "service_completed_successfully"
+            })
+        })
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Service {
-    // TODO: Check "https://serde.rs/string-or-struct.html" for how to handle "build"
-    //build: Option<String>,
-    //command: Option<String>,
-    container_name: Option<String>,
-    depends_on: Option<std::collections::BTreeMap<String, ServiceDependsOn>>,
-    environment: Option<std::collections::BTreeMap<String, String>>,
-    image: Option<String>,
-    init: Option<bool>,
-    labels: Option<std::collections::BTreeMap<String, String>>,
-    networks: Option<std::collections::BTreeMap<String, Option<String>>>,
-    ports: Option<Vec<ServicePorts>>,
-    secrets: Option<Vec<ServiceSecret>>,
-    volumes: Option<Vec<ServiceVolume>>,
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Service {
+    #[serde(default, deserialize_with = "short_or_long", skip_serializing_if = "Option::is_none")]
+    pub build: Option<ServiceBuild>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<StringOrList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    #[serde(default, deserialize_with = "depends_on", skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<BTreeMap<String, ServiceDependsOn>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<StringOrList>,
+    #[serde(default, deserialize_with = "environment", skip_serializing_if = "Option::is_none")]
+    pub environment: Option<BTreeMap<String, String>>,
+    #[serde(default, deserialize_with = "short_or_long", skip_serializing_if = "Option::is_none")]
+    pub extends: Option<ServiceExtends>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<ServiceHealthcheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init: Option<bool>,
+    #[serde(default, deserialize_with = "labels", skip_serializing_if = "Option::is_none")]
+    pub labels: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_mode: Option<String>,
+    #[serde(default, deserialize_with = "networks", skip_serializing_if = "Option::is_none")]
+    pub networks: Option<BTreeMap<String, Option<ServiceNetwork>>>,
+    #[serde(default, deserialize_with = "ports", skip_serializing_if = "Option::is_none")]
+    pub ports: Option<Vec<ServicePorts>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "short_or_long_list", skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<Vec<ServiceSecret>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, deserialize_with = "short_or_long_list", skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<Vec<ServiceVolume>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+}
+
+impl Service {
+    /// Translates a path on the host into the path inside the container, if
+    /// it's inside one of the bind mounts of the service. The most specific
+    /// mount wins when mounts are nested.
+    pub fn container_path(&self, host_path: &std::path::Path) -> Option<String> {
+        self.volumes
+            .iter()
+            .flatten()
+            .filter(|volume| volume.volume_type == "bind")
+            .filter_map(|volume| {
+                let relative = host_path.strip_prefix(&volume.source).ok()?;
+                Some((volume.source.len(), volume, relative))
+            })
+            .max_by_key(|(source_length, _, _)| *source_length)
+            .map(|(_, volume, relative)| {
+                relative.components().fold(volume.target.trim_end_matches('/').to_string(), |path, component| {
+                    format!("{}/{}", path, component.as_os_str().to_string_lossy())
+                })
+            })
+            .map(|path| if path.is_empty() { String::from("/") } else { path })
+    }
+}
+
+
+/// A value that may be given as a single string or as a list of strings, e.g.
+/// `command: npm start` or `command: [npm, start]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StringOrList {
+    String(String),
+    List(Vec<String>),
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceDependsOn {
-    condition: String,
-    required: bool
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceBuild {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+    #[serde(default, deserialize_with = "labels", skip_serializing_if = "Option::is_none")]
+    pub args: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl FromStr for ServiceBuild {
+    type Err = String;
+
+    /// The short syntax only gives the context
+    fn from_str(context: &str) -> Result<Self, Self::Err> {
+        Ok(ServiceBuild {
+            context: Some(context.to_string()),
+            ..Default::default()
+        })
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServicePorts {
-    mode: String,
-    target: u16,
-    published: String,
-    protocol: String,
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServiceDependsOn {
+    #[serde(default = "ServiceDependsOn::default_condition")]
+    pub condition: String,
+    #[serde(default = "ServiceDependsOn::default_required")]
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart: Option<bool>,
+}
+
+impl ServiceDependsOn {
+    fn default_condition() -> String {
+        String::from("service_started")
+    }
+
+    fn default_required() -> bool {
+        true
+    }
+}
+
+impl Default for ServiceDependsOn {
+    fn default() -> Self {
+        ServiceDependsOn {
+            condition: Self::default_condition(),
+            required: Self::default_required(),
+            restart: None,
+        }
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceSecret {
-    source: String,
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServiceExtends {
+    pub service: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl FromStr for ServiceExtends {
+    type Err = String;
+
+    /// The short syntax extends a service of the same file
+    fn from_str(service: &str) -> Result<Self, Self::Err> {
+        Ok(ServiceExtends {
+            service: service.to_string(),
+            file: None,
+        })
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceHealthcheck {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test: Option<StringOrList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_period: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable: Option<bool>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceNetwork {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv4_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_address: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServicePorts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default = "ServicePorts::default_mode")]
+    pub mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_ip: Option<String>,
+    pub target: u16,
+    /// Empty when docker picks a random port
+    #[serde(default, deserialize_with = "string_or_number", skip_serializing_if = "String::is_empty")]
+    pub published: String,
+    #[serde(default = "ServicePorts::default_protocol")]
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_protocol: Option<String>,
+}
+
+impl ServicePorts {
+    fn default_mode() -> String {
+        String::from("ingress")
+    }
+
+    fn default_protocol() -> String {
+        String::from("tcp")
+    }
+
+    /// Parses the short syntax, e.g. "8080:80", "127.0.0.1:53:53/udp" or
+    /// "3000-3001:3000-3001". Ranges are split into one port each.
+    pub fn parse(definition: &str) -> Result<Vec<Self>, String> {
+        let invalid = || format!("invalid port \"{}\"", definition);
+        let (ports, protocol) = definition.split_once('/').unwrap_or((definition, "tcp"));
+        let (host, target) = ports.rsplit_once(':').unwrap_or(("", ports));
+        // The host IP may be an IPv6 address in brackets, e.g. "[::1]:8080:80"
+        let (host_ip, published) = match host.rsplit_once(':') {
+            Some((host_ip, published)) => (Some(host_ip.trim_start_matches('[').trim_end_matches(']')), published),
+            None => (None, host),
+        };
+
+        let targets = port_range(target).ok_or_else(invalid)?;
+        let published: Vec<String> = match published {
+            "" => vec![String::new(); targets.len()],
+            published => match port_range(published) {
+                Some(range) if range.len() == targets.len() => range.iter().map(u16::to_string).collect(),
+                // A range of host ports for a single container port is left to docker
+                Some(_) if targets.len() == 1 => vec![published.to_string()],
+                _ => return Err(invalid()),
+            },
+        };
+
+        Ok(targets
+            .into_iter()
+            .zip(published)
+            .map(|(target, published)| ServicePorts {
+                name: None,
+                mode: Self::default_mode(),
+                host_ip: host_ip.map(String::from),
+                target,
+                published,
+                protocol: protocol.to_string(),
+                app_protocol: None,
+            })
+            .collect())
+    }
+}
+
+fn port_range(range: &str) -> Option<Vec<u16>> {
+    match range.split_once('-') {
+        Some((start, end)) => Some((start.parse().ok()?..=end.parse().ok()?).collect()),
+        None => Some(vec![range.parse().ok()?]),
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceVolume {
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServiceSecret {
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+impl FromStr for ServiceSecret {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Ok(ServiceSecret {
+            source: source.to_string(),
+            target: None,
+            uid: None,
+            gid: None,
+            mode: None,
+        })
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServiceVolume {
     #[serde(rename = "type")]
-    volume_type: String,
-    source: String,
-    target: String,
-    bind: Option<ServiceVolumeBind>,
-    // TODO: Don't know the actual type of this
-    volume: Option<std::collections::BTreeMap<String, String>>,
+    pub volume_type: String,
+    /// Empty for anonymous volumes
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source: String,
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind: Option<ServiceVolumeBind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<ServiceVolumeVolume>,
+}
+
+impl FromStr for ServiceVolume {
+    type Err = String;
+
+    /// Parses the short syntax, e.g. "./src:/app:ro", "data:/data" or "/cache".
+    /// Sources that are paths are bind mounts, other sources are named volumes.
+    fn from_str(definition: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = definition.split(':').collect();
+        let (source, target, options) = match parts.as_slice() {
+            [target] => ("", *target, ""),
+            [source, target] => (*source, *target, ""),
+            [source, target, options] => (*source, *target, *options),
+            _ => return Err(format!("invalid volume \"{}\"", definition)),
+        };
+        let options: Vec<&str> = options.split(',').collect();
+        let is_bind = source.starts_with(['.', '/', '~']);
+
+        Ok(ServiceVolume {
+            volume_type: String::from(if is_bind { "bind" } else { "volume" }),
+            source: source.to_string(),
+            target: target.to_string(),
+            read_only: options.contains(&"ro").then_some(true),
+            bind: is_bind.then(|| ServiceVolumeBind {
+                create_host_path: true,
+                selinux: options.iter().find(|option| **option == "z" || **option == "Z").map(|option| option.to_string()),
+                propagation: None,
+            }),
+            volume: (!is_bind && options.contains(&"nocopy")).then_some(ServiceVolumeVolume {
+                nocopy: Some(true),
+                subpath: None,
+            }),
+        })
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceVolumeBind {
-    create_host_path: bool,
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceVolumeBind {
+    #[serde(default)]
+    pub create_host_path: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selinux: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub propagation: Option<String>,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Network {
-    name: String,
-    external: Option<bool>,
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceVolumeVolume {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nocopy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subpath: Option<String>,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Volume {
-    name: String,
-    driver: Option<String>,
-    external: Option<bool>,
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Network {
+    /// Filled in by docker compose, which prefixes it with the project name
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Secret {
-    name: String,
-    file: String,
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Volume {
+    /// Filled in by docker compose, which prefixes it with the project name
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Secret {
+    /// Filled in by docker compose, which prefixes it with the project name
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+}
+
+/// Deserializes a value that may also be given in its short string syntax.
+fn short_or_long<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + FromStr<Err = String>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Value::String(short)) => T::from_str(&short).map(Some).map_err(D::Error::custom),
+        Some(long) => serde_yaml::from_value(long).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// Deserializes a list whose entries may also use the short string syntax.
+fn short_or_long_list<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + FromStr<Err = String>,
+{
+    let Some(values) = Option::<Vec<Value>>::deserialize(deserializer)? else { return Ok(None) };
+    values
+        .into_iter()
+        .map(|value| match value {
+            Value::String(short) => T::from_str(&short).map_err(D::Error::custom),
+            long => serde_yaml::from_value(long).map_err(D::Error::custom),
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Deserializes ports in the short or long syntax.
+pub fn ports<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<ServicePorts>>, D::Error> {
+    let Some(values) = Option::<Vec<Value>>::deserialize(deserializer)? else { return Ok(None) };
+    let mut ports = Vec::new();
+    for value in values {
+        match value {
+            Value::String(short) => ports.extend(ServicePorts::parse(&short).map_err(D::Error::custom)?),
+            Value::Number(short) => ports.extend(ServicePorts::parse(&short.to_string()).map_err(D::Error::custom)?),
+            long => ports.push(serde_yaml::from_value(long).map_err(D::Error::custom)?),
+        }
+    }
+    Ok(Some(ports))
+}
+
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(string) => Ok(string),
+        Value::Number(number) => Ok(number.to_string()),
+        _ => Err(D::Error::custom("expected a string or a number")),
+    }
+}
+
+/// Reads a list of `KEY=VALUE` strings or a mapping, the same way the loader does.
+fn key_values<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BTreeMap<String, Option<String>>>, D::Error> {
+    Option::<Value>::deserialize(deserializer)?
+        .map(compose_loader::key_values)
+        .transpose()
+        .map_err(D::Error::custom)
+}
+
+/// Variables without a value are resolved from the shell by the loader (or
+/// `docker compose config`), so any left are unset and left out.
+fn environment<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BTreeMap<String, String>>, D::Error> {
+    Ok(key_values(deserializer)?.map(|environment| {
+        environment
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect()
+    }))
+}
+
+/// Labels without a value are empty.
+fn labels<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BTreeMap<String, String>>, D::Error> {
+    Ok(key_values(deserializer)?.map(|labels| {
+        labels.into_iter().map(|(key, value)| (key, value.unwrap_or_default())).collect()
+    }))
+}
+
+fn depends_on<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BTreeMap<String, ServiceDependsOn>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Syntax {
+        List(Vec<String>),
+        Map(BTreeMap<String, Option<ServiceDependsOn>>),
+    }
+    Ok(Option::<Syntax>::deserialize(deserializer)?.map(|syntax| match syntax {
+        Syntax::List(services) => services.into_iter().map(|service| (service, ServiceDependsOn::default())).collect(),
+        Syntax::Map(services) => services.into_iter().map(|(service, depends_on)| (service, depends_on.unwrap_or_default())).collect(),
+    }))
+}
+
+fn networks<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BTreeMap<String, Option<ServiceNetwork>>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Syntax {
+        List(Vec<String>),
+        Map(BTreeMap<String, Option<ServiceNetwork>>),
+    }
+    Ok(Option::<Syntax>::deserialize(deserializer)?.map(|syntax| match syntax {
+        Syntax::List(networks) => networks.into_iter().map(|network| (network, None)).collect(),
+        Syntax::Map(networks) => networks,
+    }))
+}
+
+/// Top level networks, volumes and secrets may be declared without any settings.
+fn definitions<'de, D, T>(deserializer: D) -> Result<Option<BTreeMap<String, T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let Some(definitions) = Option::<BTreeMap<String, Option<T>>>::deserialize(deserializer)? else { return Ok(None) };
+    Ok(Some(definitions.into_iter().map(|(key, definition)| (key, definition.unwrap_or_default())).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn startup_order_respects_depends_on() {
+        let config: Config = serde_yaml::from_str(r#"
+name: test
+services:
+  nginx:
+    depends_on:
+      php: {condition: service_started, required: true}
+  php:
+    depends_on:
+      db: {condition: service_healthy, required: true}
+      migrations: {condition: service_completed_successfully, required: true}
+  migrations:
+    depends_on:
+      db: {condition: service_healthy, required: true}
+  db: {}
+"#).unwrap();
+
+        assert_eq!(config.startup_order(), vec!["db", "migrations", "php", "nginx"]);
+        assert!(config.runs_to_completion("migrations"));
+        assert!(!config.runs_to_completion("db"));
+        assert_eq!(config.with_dependents(&[String::from("migrations")]), vec!["migrations", "php", "nginx"]);
+        assert_eq!(config.with_dependencies(&[String::from("php")]), vec!["db", "migrations", "php"]);
+    }
+
+    #[test]
+    fn resolve_service_names() {
+        let mut config: Config = serde_yaml::from_str(r#"
+services:
+  db: {}
+  php:
+    labels: {dev-cli.default: "true"}
+  nginx: {}
+"#).unwrap();
+        let app_config: AppConfig = serde_yaml::from_str("service_aliases: {app: php, web: nginx}").unwrap();
+
+        assert_eq!(config.resolve_service(&app_config, None).unwrap(), "php");
+        assert_eq!(config.resolve_service(&app_config, Some("web")).unwrap(), "nginx");
+        assert_eq!(config.resolve_service(&app_config, Some("db")).unwrap(), "db");
+        assert_eq!(config.resolve_service(&app_config, Some("nginz")).unwrap_err(), "The service 'nginz' does not exist, did you mean 'nginx'?");
+        assert_eq!(config.resolve_service(&app_config, Some("redis")).unwrap_err(), "The service 'redis' does not exist");
+
+        let app_config: AppConfig = serde_yaml::from_str("{default_service: web, service_aliases: {web: nginx}}").unwrap();
+        assert_eq!(config.resolve_service(&app_config, None).unwrap(), "nginx");
+
+        config.services.get_mut("php").unwrap().labels = None;
+        assert_eq!(config.resolve_service(&AppConfig::default(), None).unwrap(), "db");
+    }
+
+    #[test]
+    fn project_volumes_skip_external_volumes() {
+        let config: Config = serde_yaml::from_str(r#"
+name: test
+services: {}
+volumes:
+  db-data:
+    name: test_db-data
+  shared:
+    name: shared
+    external: true
+"#).unwrap();
+
+        let volumes: Vec<&str> = config.project_volumes().iter().map(|volume| volume.name.as_str()).collect();
+        assert_eq!(volumes, vec!["test_db-data"]);
+    }
+
+    #[test]
+    fn compose_files_from_config() {
+        let project = assert_fs::TempDir::new().unwrap();
+        let mut app_config = AppConfig::default();
+        assert!(DockerCompose::from_config(project.path(), &app_config).is_err());
+
+        for file in ["docker-compose.yml", "compose.yaml", "compose.override.yml", "compose.dev.yml"] {
+            std::fs::write(project.path().join(file), "services: {}\n").unwrap();
+        }
+        let docker_compose = DockerCompose::from_config(project.path(), &app_config).unwrap();
+        assert_eq!(docker_compose.files, vec![project.path().join("compose.yaml"), project.path().join("compose.override.yml")]);
+
+        app_config.compose_files = Some(vec![String::from("compose.yaml"), String::from("compose.dev.yml")]);
+        app_config.compose_profiles = Some(vec![String::from("debug")]);
+        app_config.compose_project_name = Some(String::from("app"));
+        let docker_compose = DockerCompose::from_config(project.path(), &app_config).unwrap();
+        assert_eq!(
+            docker_compose.command().arg("up").to_cmdline_lossy(),
+            format!(
+                "docker compose --file {0}/compose.yaml --file {0}/compose.dev.yml --profile debug --project-name app up",
+                project.path().display()
+            )
+        );
+
+        app_config.compose_files = Some(vec![String::from("compose.prod.yml")]);
+        assert!(DockerCompose::from_config(project.path(), &app_config).is_err());
+    }
+
+    #[test]
+    fn exec_keeps_values_out_of_arguments() {
+        let docker_compose = DockerCompose::new(PathBuf::from("/project"), Vec::new(), Vec::new(), None);
+        let env = [(String::from("MYSQL_PWD"), String::from("secret"))];
+        assert_eq!(
+            docker_compose.exec_command("db", None, None, &env, false).arg("mysql").to_cmdline_lossy(),
+            // The value is only set in the environment of docker compose
+            "MYSQL_PWD=secret docker compose exec --no-TTY --env MYSQL_PWD db mysql"
+        );
+    }
+
+    #[test]
+    fn short_syntax() {
+        let ports = |port: &str| serde_yaml::to_string(&ServicePorts::parse(port).unwrap()).unwrap();
+        assert_eq!(ports("8080:80"), "- mode: ingress\n  target: 80\n  published: '8080'\n  protocol: tcp\n");
+        assert_eq!(ports("127.0.0.1:53:53/udp"), "- mode: ingress\n  host_ip: 127.0.0.1\n  target: 53\n  published: '53'\n  protocol: udp\n");
+        assert_eq!(ports("3000-3001"), "- mode: ingress\n  target: 3000\n  protocol: tcp\n- mode: ingress\n  target: 3001\n  protocol: tcp\n");
+        assert!(ServicePorts::parse("80:90-91").is_err());
+
+        let service: Service = serde_yaml::from_str(r#"
+build: ./docker/php
+command: php-fpm --nodaemonize
+entrypoint: [docker-entrypoint.sh]
+environment: [APP_ENV=dev, "DEBUG=1"]
+extends: base
+healthcheck:
+  test: [CMD, php-fpm-healthcheck]
+  interval: 10s
+  retries: 3
+labels:
+  com.example.port: 8080
+  com.example.enabled: true
+depends_on: [db]
+networks: [default]
+ports: [80, "443:443"]
+profiles: [debug]
+secrets: [token]
+user: www-data
+volumes: ["./src:/var/www/html:z", "data:/data:nocopy"]
+working_dir: /var/www/html
+"#).unwrap();
+
+        assert_eq!(service.build.as_ref().unwrap().context.as_deref(), Some("./docker/php"));
+        assert_eq!(service.command, Some(StringOrList::String(String::from("php-fpm --nodaemonize"))));
+        assert_eq!(service.entrypoint, Some(StringOrList::List(vec![String::from("docker-entrypoint.sh")])));
+        assert_eq!(service.environment.as_ref().unwrap()["DEBUG"], "1");
+        assert_eq!(service.extends.as_ref().unwrap().service, "base");
+        assert_eq!(service.healthcheck.as_ref().unwrap().retries, Some(3));
+        assert_eq!(service.labels.as_ref().unwrap()["com.example.enabled"], "true");
+        assert_eq!(service.depends_on.as_ref().unwrap()["db"], ServiceDependsOn::default());
+        assert_eq!(service.networks.as_ref().unwrap()["default"], None);
+        let published: Vec<&str> = service.ports.iter().flatten().map(|port| port.published.as_str()).collect();
+        assert_eq!(published, vec!["", "443"]);
+        assert_eq!(service.secrets.as_ref().unwrap()[0].source, "token");
+        let volumes = service.volumes.as_ref().unwrap();
+        assert_eq!(volumes[0].bind.as_ref().unwrap().selinux.as_deref(), Some("z"));
+        assert_eq!(volumes[1].volume.as_ref().unwrap().nocopy, Some(true));
+        assert_eq!(service.working_dir.as_deref(), Some("/var/www/html"));
+
+        // Serializing gives the long syntax, which reads back the same
+        let long = serde_yaml::to_string(&service).unwrap();
+        assert_eq!(serde_yaml::from_str::<Service>(&long).unwrap(), service);
+    }
+
+    #[test]
+    fn example_compose_files_round_trip() {
+        let examples = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        let mut count = 0;
+        for example in std::fs::read_dir(examples).unwrap() {
+            let file = example.unwrap().path().join("compose.yml");
+            if !file.is_file() {
+                continue;
+            }
+            let content = std::fs::read_to_string(&file).unwrap();
+            let config: Config = serde_yaml::from_str(&content).unwrap_or_else(|error| panic!("{}: {}", file.display(), error));
+            assert!(!config.services.is_empty(), "{}", file.display());
+
+            let serialized = serde_yaml::to_string(&config).unwrap();
+            assert_eq!(serde_yaml::from_str::<Config>(&serialized).unwrap(), config, "{}", file.display());
+
+            // The native loader gives the same model as reading the file directly, plus the names it derives
+            let loaded: Config = serde_yaml::from_value(compose_loader::load(std::slice::from_ref(&file), &[], Some("example"), []).unwrap()).unwrap();
+            assert_eq!(loaded.services.keys().collect::<Vec<_>>(), config.services.keys().collect::<Vec<_>>(), "{}", file.display());
+            count += 1;
+        }
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn subfolder_example_workdir() {
+        let example = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/use-subfolder");
+        let config: Config = serde_yaml::from_value(compose_loader::load(&[example.join("compose.yml")], &[], None, []).unwrap()).unwrap();
+
+        let nginx = &config.services["nginx"];
+        assert_eq!(nginx.container_path(&example.join("some-folder")), Some(String::from("/usr/share/nginx/html/some-folder")));
+        assert_eq!(nginx.container_path(example.parent().unwrap()), None);
+    }
+
+    #[test]
+    fn mounted_workdir_before_default() {
+        // Tests run in the crate directory
+        let config: Config = serde_yaml::from_str(&format!(r#"
+services:
+  mounted:
+    volumes: [{{type: bind, source: "{}", target: /app}}]
+  other: {{}}
+"#, env!("CARGO_MANIFEST_DIR"))).unwrap();
+
+        assert_eq!(config.workdir("mounted", Some("/srv")), Some(String::from("/app")));
+        assert_eq!(config.workdir("other", Some("/srv")), Some(String::from("/srv")));
+        assert_eq!(config.workdir("other", None), None);
+    }
+
+    #[test]
+    fn container_path_follows_bind_mounts() {
+        let service: Service = serde_yaml::from_str(r#"
+volumes:
+  - {type: bind, source: /home/dev/project, target: /app}
+  - {type: bind, source: /home/dev/project/src, target: /var/www/html}
+  - {type: volume, source: data, target: /data}
+"#).unwrap();
+
+        let path = |host: &str| service.container_path(std::path::Path::new(host));
+        assert_eq!(path("/home/dev/project"), Some(String::from("/app")));
+        assert_eq!(path("/home/dev/project/docs/api"), Some(String::from("/app/docs/api")));
+        assert_eq!(path("/home/dev/project/src/public"), Some(String::from("/var/www/html/public")));
+        assert_eq!(path("/home/dev/other"), None);
+    }
 }
@@ -1,126 +1,314 @@
 use bollard::Docker;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::collections::HashMap;
 use bollard::network::{CreateNetworkOptions, ListNetworksOptions};
 
+/// The docker network shared by all projects and the dev-cli containers (Traefik, etc.),
+/// which the compose files join as an external network
+pub const SHARED_NETWORK: &str = "dev-cli_web";
+
 #[derive(Debug, Parser)]
 #[command(version, about = "A CLI for managing local Docker development environments", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Cli {
-    /// The name of the service to run the command in. If omitted, the first service in the project will be used.
+    /// The name or alias of the service to run the command in. If omitted, the `default_service` from the config is used.
     #[arg(short, long)]
     pub service: Option<String>,
 
     /// Run the command in offline mode. This will prevent dev-cli from trying to connect to the internet.
-    pub offline: Option<bool>,
+    #[arg(long, global = true)]
+    pub offline: bool,
 
     #[command(subcommand)]
     pub command: Option<Commands>,
 
+    /// The command to execute in the service, e.g. `dev-cli composer install`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub exec_command: Vec<String>,
 }
 
 #[derive(Debug, Clone, Subcommand, PartialEq)]
 pub enum Commands {
     /// Initialize a new project for dev-cli using pre-defined templates
-    Init,
-    /// Starts a docker compose project
-    Start,
+    Init {
+        /// The template to use. If omitted, you can choose one interactively.
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Overwrite files that already exist
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Starts a docker compose project and waits until all services are ready
+    Start {
+        /// How many seconds to wait for the services to become ready
+        #[arg(long, default_value_t = 120)]
+        timeout: u64,
+    },
     /// Stop and remove the containers of a project. Does not lose or harm anything unless you add --remove-data.
     Stop {
+        /// Also remove the volumes of the project, deleting all data stored in them (external volumes are kept)
         #[arg(long, default_value("false"))]
         remove_data: bool,
+
+        /// Don't ask for confirmation before removing data
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Stops, removes and starts a project again
-    Restart,
+    Restart {
+        /// Only recreate these services (and the services depending on them)
+        services: Vec<String>,
+
+        /// How many seconds to wait for the services to become ready
+        #[arg(long, default_value_t = 120)]
+        timeout: u64,
+    },
     /// Stop all projects and dev-cli containers (Traefik, etc.)
-    Poweroff,
+    Poweroff {
+        /// Only show what would be stopped
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Execute a shell command in the container for a service.
     Exec {
+        /// The name or alias of the service to run the command in. If omitted, the `default_service` from the config is used.
         #[arg(short, long)]
         service: Option<String>,
 
+        /// The user to run the command as
         #[arg(short, long)]
         user: Option<String>,
 
+        /// Set an environment variable, overriding the one from the config
+        #[arg(short, long = "env", value_name = "KEY=VAL", value_parser = parse_env)]
+        env: Vec<(String, String)>,
+
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
         command: Vec<String>,
     },
-    /// Run a command defined in the config file
+    /// Run a command defined in the config file. Lists all commands if no name is given.
     Run {
-        command: Vec<String>,
+        name: Option<String>,
     },
     /// Starts a shell session in the container for a service
-    Shell,
+    Shell {
+        /// The service to start the shell in. If omitted, the same service as for exec is used.
+        service: Option<String>,
+
+        #[arg(short, long)]
+        user: Option<String>,
+    },
     /// Launches the default URL in the default browser
-    Launch,
+    Launch {
+        /// Only print the URL instead of opening it
+        #[arg(long)]
+        print: bool,
+    },
     /// Show the status of the containers of this project
     Status,
     /// Show the status of all projects that ran through dev-cli
     GlobalStatus,
+    /// Dump a database to a file or to stdout
+    ExportDb {
+        /// The file to write to, relative to the dumps directory. Use "-" for stdout.
+        #[arg(short, long)]
+        file: Option<String>,
+
+        /// Compress the dump with gzip
+        #[arg(long, conflicts_with = "zstd")]
+        gzip: bool,
+
+        /// Compress the dump with zstd
+        #[arg(long)]
+        zstd: bool,
+    },
+    /// Import a SQL dump file into the project
+    ImportDb {
+        /// The .sql, .sql.gz or .sql.zst file to import. If omitted, the newest dump in the dumps directory is offered.
+        file: Option<std::path::PathBuf>,
+
+        /// Drop and recreate the database before importing
+        #[arg(long)]
+        drop: bool,
+    },
+    /// Create, list and restore database snapshots of the project
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+    /// Show or change the configuration of dev-cli
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Get the logs from your running services.
+    Logs {
+        /// The services to show the logs of. If omitted, all services are shown.
+        services: Vec<String>,
+
+        /// Keep streaming new log lines, also across container restarts
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Only show logs since a relative time (e.g. 30s, 10m, 2h, 1d) or a UNIX timestamp
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Number of lines to show from the end of the logs of each container
+        #[arg(short = 'n', long)]
+        tail: Option<String>,
+
+        /// Only show lines matching this regular expression
+        #[arg(short, long)]
+        grep: Option<String>,
+
+        /// Show the timestamp of every line
+        #[arg(short, long)]
+        timestamps: bool,
+    },
 
 
     // Removes items dev-cli has created
     //Clean,
     // Generate the autocompletion script for the specified shell
     //Completion,
-    // Create or modify a dev-cli project configuration in the current directory
-    //Config,
     // Remove all project information (including database) for an existing project
     //Delete,
     // Get a detailed description of a running dev-cli project
     //Describe,
-    // Dump a database to a file or to stdout
-    //ExportDb,
     // Get/Download a 3rd party add-on (service, provider, etc.)
     //Get,
     // Manage your hostfile entries.
     //Hostname,
-    // Import a SQL dump file into the project
-    //ImportDb,
     // Pull the uploaded files directory of an existing project to the default public upload directory of your project
     //ImportFiles,
     // List projects
     //List,
-    // Get the logs from your running services.
-    //Logs,
     // Add or remove, enable or disable extra services
     //Service,
-    // Create a database snapshot for one or more projects.
-    //Snapshot,
+}
+
+#[derive(Debug, Clone, Subcommand, PartialEq)]
+pub enum SnapshotCommands {
+    /// Create a snapshot of the database. If no name is given, the current time is used.
+    Create {
+        name: Option<String>,
+    },
+    /// List all snapshots of the project
+    List,
+    /// Replace the database with a snapshot
+    Restore {
+        name: String,
+    },
+    /// Remove the oldest snapshots
+    Prune {
+        /// How many of the newest snapshots to keep
+        #[arg(long)]
+        keep: usize,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand, PartialEq)]
+pub enum ConfigCommands {
+    /// Print the effective configuration, after merging all config files and environment variables
+    Show {
+        /// Annotate every value with the file or environment variable it comes from
+        #[arg(long)]
+        origin: bool,
+
+        #[arg(value_enum, default_value_t = ConfigFormat::Yaml)]
+        format: ConfigFormat,
+    },
+    /// Print a single value of the effective configuration, e.g. "services.php.user"
+    Get {
+        key: String,
+    },
+    /// Change a value in one of the config files, keeping its comments and formatting
+    Set {
+        key: String,
+
+        value: String,
+
+        /// Change the local .dev-cli.yml (default)
+        #[arg(long, conflicts_with_all = ["project", "global"])]
+        local: bool,
+
+        /// Change the .dev-cli.dist.yml shared with the project
+        #[arg(long, conflicts_with = "global")]
+        project: bool,
+
+        /// Change the global config file
+        #[arg(long)]
+        global: bool,
+    },
+    /// Print the JSON Schema of the config files, for validation in editors
+    Schema,
+    /// Print the compose config of the project as dev-cli reads it
+    Compose {
+        /// Show the differences to the output of `docker compose config` instead
+        #[arg(long)]
+        compare: bool,
+    },
+    /// Check the config files for errors. If no files are given, all config files of the project are checked.
+    Validate {
+        files: Vec<std::path::PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
 }
 
 impl Commands {
     pub fn requires_docker(&self) -> bool {
-        match self {
-            Commands::Start
+        matches!(
+            self,
+            Commands::Start { .. }
             | Commands::Stop { .. }
-            | Commands::Restart
-            | Commands::Poweroff
+            | Commands::Restart { .. }
+            | Commands::Poweroff { .. }
             | Commands::Exec { .. }
             | Commands::Run { .. }
-            | Commands::Shell
+            | Commands::Shell { .. }
             | Commands::Status
-            | Commands::GlobalStatus => true,
-            _ => false
-        }
+            | Commands::GlobalStatus
+            | Commands::Logs { .. }
+            | Commands::ExportDb { .. }
+            | Commands::ImportDb { .. }
+            | Commands::Snapshot { .. }
+        )
     }
 }
 
 pub fn is_docker_required(
     command: &Option<Commands>,
-    exec_command: &Vec<String>,
+    exec_command: &[String],
 ) -> bool {
     let required_by_command = match command {
         Some(command) => command.requires_docker(),
         None => false,
     };
-    required_by_command || exec_command.len() > 0
+    required_by_command || !exec_command.is_empty()
+}
+
+/// Converts the exit status of a child process into an exit code for dev-cli.
+pub fn exit_code(status: subprocess::ExitStatus) -> i32 {
+    match status {
+        subprocess::ExitStatus::Exited(code) => code as i32,
+        subprocess::ExitStatus::Signaled(code) => 128 + code as i32,
+        subprocess::ExitStatus::Other(code) => code,
+        subprocess::ExitStatus::Undetermined => 1,
+    }
 }
 
 pub async fn docker_running(docker: &Docker) -> String {
     match docker.ping().await {
         Ok(result) => result,
         Err(error) => {
-            println!("Docker doesn't seem to be turned on ({})", error);
+            eprintln!("Docker doesn't seem to be turned on ({})", error);
             sysexits::ExitCode::OsErr.exit()
             //Err(anyhow::anyhow!("Docker doesn't seem to be turned on ({})", error))
         }
@@ -214,9 +402,9 @@ pub fn check_and_setup_system() {
 }
 
 pub async fn check_and_setup_docker(docker: &bollard::Docker) {
-    // Check that the docker network "dev-cli-web" exists using bollard
+    // Check that the shared docker network exists using bollard
     let mut list_networks_filters = HashMap::new();
-    list_networks_filters.insert("name", vec!["dev-cli-web"]);
+    list_networks_filters.insert("name", vec![SHARED_NETWORK]);
     let config = ListNetworksOptions {
         filters: list_networks_filters,
     };
@@ -225,25 +413,69 @@ pub async fn check_and_setup_docker(docker: &bollard::Docker) {
     match networks {
         Ok(networks) => {
             if networks.is_empty() {
-                println!("Creating the network 'dev-cli-web'...");
+                eprintln!("Creating the network '{}'...", SHARED_NETWORK);
                 let config = CreateNetworkOptions {
-                    name: "dev-cli-web",
+                    name: SHARED_NETWORK,
                     ..Default::default()
                 };
 
                 let network_created = docker.create_network(config).await;
                 match network_created {
-                    Ok(_) => println!("Network 'dev-cli-web' created successfully"),
+                    Ok(_) => eprintln!("Network '{}' created successfully", SHARED_NETWORK),
                     Err(error) => {
-                        println!("Could not create the network 'dev-cli-web': {}", error);
+                        eprintln!("Could not create the network '{}': {}", SHARED_NETWORK, error);
                         sysexits::ExitCode::OsErr.exit()
                     }
                 }
             }
         }
         Err(error) => {
-            println!("Could not list networks: {}", error);
+            eprintln!("Could not list networks: {}", error);
             sysexits::ExitCode::OsErr.exit()
         }
     }
 }
+
+/// Parses an environment variable given as `KEY=VAL`
+fn parse_env(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VAL, got '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from([&["dev-cli"], args].concat()).unwrap()
+    }
+
+    #[test]
+    fn exec_arguments() {
+        let cli = parse(&["exec", "-s", "php", "-u", "root", "-e", "APP_ENV=test", "--env", "EMPTY=", "ls", "-la"]);
+        assert_eq!(cli.command, Some(Commands::Exec {
+            service: Some(String::from("php")),
+            user: Some(String::from("root")),
+            env: vec![(String::from("APP_ENV"), String::from("test")), (String::from("EMPTY"), String::new())],
+            command: vec![String::from("ls"), String::from("-la")],
+        }));
+
+        let cli = parse(&["exec", "ls"]);
+        assert!(matches!(cli.command, Some(Commands::Exec { service: None, .. })));
+
+        let cli = parse(&["-s", "node", "yarn", "--version"]);
+        assert_eq!(cli.service.as_deref(), Some("node"));
+        assert_eq!(cli.command, None);
+        assert_eq!(cli.exec_command, vec!["yarn", "--version"]);
+
+        let cli = parse(&["start", "--offline"]);
+        assert!(cli.offline);
+        assert_eq!(cli.command, Some(Commands::Start { timeout: 120 }));
+        assert!(parse(&["--offline", "status"]).offline);
+
+        assert!(Cli::try_parse_from(["dev-cli", "exec", "-s", "php"]).is_err());
+        assert!(Cli::try_parse_from(["dev-cli", "exec", "-e", "APP_ENV", "ls"]).is_err());
+    }
+}
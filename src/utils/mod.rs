@@ -1,4 +1,18 @@
 pub mod general;
 pub mod app_config;
+pub mod compose_loader;
+pub mod compression;
+pub mod container;
+pub mod database;
 pub mod docker_compose;
 pub mod path;
+pub mod prompt;
+pub mod readiness;
+pub mod registry;
+pub mod snapshot;
+pub mod suggest;
+pub mod table;
+pub mod templates;
+pub mod time;
+pub mod traefik;
+pub mod yaml_edit;
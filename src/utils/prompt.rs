@@ -0,0 +1,34 @@
+use std::io::Write;
+
+/// Asks a yes/no question on the terminal. Anything but an explicit "y" or
+/// "yes" counts as no, so nothing happens by accident (e.g. when stdin is closed).
+pub fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Lets the user pick one of the options by its number or name. Returns the
+/// index of the picked option, or None if the answer matches no option.
+pub fn select(question: &str, options: &[(&str, &str)]) -> Option<usize> {
+    println!("{}", question);
+    let width = options.iter().map(|(name, _)| name.len()).max().unwrap_or_default();
+    for (index, (name, description)) in options.iter().enumerate() {
+        println!("  {}) {:<width$}  {}", index + 1, name, description, width = width);
+    }
+    print!("Choose [1-{}]: ", options.len());
+    std::io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    let answer = answer.trim();
+    match answer.parse::<usize>() {
+        Ok(number) if (1..=options.len()).contains(&number) => Some(number - 1),
+        _ => options.iter().position(|(name, _)| *name == answer),
+    }
+}
@@ -0,0 +1,239 @@
+use bollard::Docker;
+use bollard::models::{ContainerInspectResponse, ContainerStateStatusEnum, HealthStatusEnum};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use crate::utils::container::{project_containers, tail_logs};
+use crate::utils::docker_compose::Config;
+
+/// How many log lines are shown for a service that didn't become ready
+const DIAGNOSIS_LOG_LINES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Readiness {
+    /// Still coming up, with a short description of what it's waiting for
+    Pending(String),
+    /// Usable, with a short description of the state (running, healthy, ...)
+    Ready(String),
+    /// Won't become ready without intervention
+    Failed(String),
+}
+
+impl std::fmt::Display for Readiness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Readiness::Pending(reason) => write!(f, "{}...", reason),
+            Readiness::Ready(state) => write!(f, "{}", state),
+            Readiness::Failed(reason) => write!(f, "failed ({})", reason),
+        }
+    }
+}
+
+/// Determines whether a single container is ready. Containers with a
+/// healthcheck have to be healthy, all others only have to be running.
+/// Services other services wait on to complete have to exit successfully.
+pub fn container_readiness(container: &ContainerInspectResponse, runs_to_completion: bool) -> Readiness {
+    let state = match &container.state {
+        Some(state) => state,
+        None => return Readiness::Pending(String::from("starting")),
+    };
+    let health = state.health.as_ref().and_then(|health| health.status);
+
+    match state.status {
+        Some(ContainerStateStatusEnum::RUNNING) => match health {
+            Some(HealthStatusEnum::STARTING) => Readiness::Pending(String::from("health: starting")),
+            Some(HealthStatusEnum::HEALTHY) => Readiness::Ready(String::from("healthy")),
+            Some(HealthStatusEnum::UNHEALTHY) => Readiness::Failed(String::from("unhealthy")),
+            _ if runs_to_completion => Readiness::Pending(String::from("running to completion")),
+            _ => Readiness::Ready(String::from("running")),
+        },
+        Some(ContainerStateStatusEnum::EXITED) => match state.exit_code {
+            Some(0) if runs_to_completion => Readiness::Ready(String::from("completed")),
+            Some(code) => Readiness::Failed(format!("exited with code {}", code)),
+            None => Readiness::Failed(String::from("exited")),
+        },
+        Some(ContainerStateStatusEnum::RESTARTING) => Readiness::Pending(String::from("restarting")),
+        Some(ContainerStateStatusEnum::DEAD) => Readiness::Failed(String::from("dead")),
+        Some(ContainerStateStatusEnum::PAUSED) => Readiness::Failed(String::from("paused")),
+        _ => Readiness::Pending(String::from("starting")),
+    }
+}
+
+/// Determines whether all containers of a service are ready.
+async fn service_readiness(
+    docker: &Docker,
+    config: &Config,
+    service: &str,
+) -> Result<Readiness, bollard::errors::Error> {
+    let containers = project_containers(docker, &config.name, Some(service)).await?;
+    if containers.is_empty() {
+        return Ok(Readiness::Pending(String::from("not created yet")));
+    }
+
+    let runs_to_completion = config.runs_to_completion(service);
+    let mut readiness = None;
+    for container in containers {
+        let inspect = docker.inspect_container(&container.id.unwrap_or_default(), None).await?;
+        match container_readiness(&inspect, runs_to_completion) {
+            failed @ Readiness::Failed(_) => return Ok(failed),
+            pending @ Readiness::Pending(_) => readiness = Some(pending),
+            ready @ Readiness::Ready(_) => {
+                readiness.get_or_insert(ready);
+            }
+        }
+    }
+
+    Ok(readiness.unwrap())
+}
+
+/// Waits until every given service is ready, printing a line to stderr
+/// whenever the state of a service changes, so it doesn't mix with the output
+/// of a command run afterwards. Services that are still waiting on one of
+/// their dependencies report that dependency instead of their own state.
+///
+/// If a service fails or the timeout is reached, a diagnosis for every
+/// service that isn't ready is printed and dev-cli exits.
+pub async fn wait_until_ready(
+    docker: &Docker,
+    config: &Config,
+    services: &[String],
+    timeout: Duration,
+) -> Result<(), bollard::errors::Error> {
+    let deadline = Instant::now() + timeout;
+    let width = services.iter().map(String::len).max().unwrap_or_default();
+    let mut states: BTreeMap<&str, Readiness> = BTreeMap::new();
+
+    loop {
+        for service in services {
+            let mut readiness = service_readiness(docker, config, service).await?;
+            if let Readiness::Pending(_) = readiness {
+                let waiting_for = config.dependencies(service).into_iter().find(|(dependency, _)| {
+                    matches!(states.get(dependency.as_str()), Some(Readiness::Pending(_)))
+                });
+                if let Some((dependency, depends_on)) = waiting_for {
+                    readiness = Readiness::Pending(format!("waiting for {} ({})", dependency, depends_on.condition));
+                }
+            }
+
+            if states.get(service.as_str()) != Some(&readiness) {
+                eprintln!("{:<width$}  {}", service, readiness, width = width);
+                states.insert(service, readiness);
+            }
+        }
+
+        let not_ready: Vec<&str> = states
+            .iter()
+            .filter(|(_, readiness)| !matches!(readiness, Readiness::Ready(_)))
+            .map(|(service, _)| *service)
+            .collect();
+        if not_ready.is_empty() {
+            return Ok(());
+        }
+
+        let failed = states.values().any(|readiness| matches!(readiness, Readiness::Failed(_)));
+        if failed || Instant::now() >= deadline {
+            if !failed {
+                eprintln!("Timed out after {} seconds waiting for the project to become ready", timeout.as_secs());
+            }
+            for service in not_ready {
+                print_diagnosis(docker, config, service).await?;
+            }
+            sysexits::ExitCode::Unavailable.exit()
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Prints the state, exit code, last healthcheck output and last log lines of
+/// every container of a service that didn't become ready.
+async fn print_diagnosis(
+    docker: &Docker,
+    config: &Config,
+    service: &str,
+) -> Result<(), bollard::errors::Error> {
+    let containers = project_containers(docker, &config.name, Some(service)).await?;
+    if containers.is_empty() {
+        eprintln!("\nService '{}' has no container", service);
+        return Ok(());
+    }
+
+    for container in containers {
+        let id = container.id.unwrap_or_default();
+        let inspect = docker.inspect_container(&id, None).await?;
+        let name = inspect.name.clone().unwrap_or(id.clone());
+        let state = inspect.state.unwrap_or_default();
+
+        eprintln!("\nService '{}' is not ready (container {})", service, name.trim_start_matches('/'));
+        if let Some(status) = state.status {
+            eprintln!("  state:     {}", status);
+        }
+        if let Some(exit_code) = state.exit_code.filter(|_| state.running != Some(true)) {
+            eprintln!("  exit code: {}", exit_code);
+        }
+        if let Some(error) = state.error.filter(|error| !error.is_empty()) {
+            eprintln!("  error:     {}", error);
+        }
+        let last_check = state.health
+            .and_then(|health| health.log)
+            .and_then(|log| log.into_iter().last());
+        if let Some(check) = last_check {
+            eprintln!("  last healthcheck (exit code {}):", check.exit_code.unwrap_or_default());
+            for line in check.output.unwrap_or_default().lines() {
+                eprintln!("    {}", line);
+            }
+        }
+
+        let logs = tail_logs(docker, &id, DIAGNOSIS_LOG_LINES).await?;
+        if !logs.is_empty() {
+            eprintln!("  last {} log lines:", logs.len());
+            for line in logs {
+                eprintln!("    {}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::models::{ContainerState, Health};
+
+    fn container(status: ContainerStateStatusEnum, health: Option<HealthStatusEnum>, exit_code: i64) -> ContainerInspectResponse {
+        ContainerInspectResponse {
+            state: Some(ContainerState {
+                status: Some(status),
+                exit_code: Some(exit_code),
+                health: health.map(|status| Health { status: Some(status), ..Default::default() }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn running_without_healthcheck_is_ready() {
+        let container = container(ContainerStateStatusEnum::RUNNING, None, 0);
+        assert_eq!(container_readiness(&container, false), Readiness::Ready(String::from("running")));
+    }
+
+    #[test]
+    fn running_with_healthcheck_waits_for_healthy() {
+        let starting = container(ContainerStateStatusEnum::RUNNING, Some(HealthStatusEnum::STARTING), 0);
+        let healthy = container(ContainerStateStatusEnum::RUNNING, Some(HealthStatusEnum::HEALTHY), 0);
+        let unhealthy = container(ContainerStateStatusEnum::RUNNING, Some(HealthStatusEnum::UNHEALTHY), 0);
+        assert!(matches!(container_readiness(&starting, false), Readiness::Pending(_)));
+        assert!(matches!(container_readiness(&healthy, false), Readiness::Ready(_)));
+        assert!(matches!(container_readiness(&unhealthy, false), Readiness::Failed(_)));
+    }
+
+    #[test]
+    fn exited_is_only_ready_when_running_to_completion() {
+        let success = container(ContainerStateStatusEnum::EXITED, None, 0);
+        let failure = container(ContainerStateStatusEnum::EXITED, None, 1);
+        assert!(matches!(container_readiness(&success, true), Readiness::Ready(_)));
+        assert!(matches!(container_readiness(&success, false), Readiness::Failed(_)));
+        assert_eq!(container_readiness(&failure, true), Readiness::Failed(String::from("exited with code 1")));
+    }
+}
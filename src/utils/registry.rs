@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::utils::time::now;
+
+/// All projects dev-cli has been used in, stored next to the global config
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    pub projects: Vec<Project>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub path: PathBuf,
+    /// UNIX timestamp of the last time dev-cli was used in the project
+    pub last_used: u64,
+}
+
+impl Registry {
+    /// Loads the registry, a missing file is an empty registry.
+    pub fn load(file: &Path) -> Result<Self> {
+        if !file.is_file() {
+            return Ok(Registry::default());
+        }
+        Ok(serde_yaml::from_reader(File::open(file)?)?)
+    }
+
+    pub fn save(&self, file: &Path) -> Result<()> {
+        if let Some(directory) = file.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        serde_yaml::to_writer(File::create(file)?, self)?;
+        Ok(())
+    }
+
+    /// Adds the project at the given root, or marks it as used now if it's
+    /// already known.
+    pub fn record(&mut self, project_root: &Path) {
+        let name = project_root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match self.projects.iter_mut().find(|project| project.path == project_root) {
+            Some(project) => {
+                project.name = name;
+                project.last_used = now();
+            }
+            None => self.projects.push(Project {
+                name,
+                path: project_root.to_path_buf(),
+                last_used: now(),
+            }),
+        }
+    }
+
+    /// Removes the projects whose directory doesn't exist anymore and returns them.
+    pub fn prune(&mut self) -> Vec<Project> {
+        let (existing, removed) = std::mem::take(&mut self.projects)
+            .into_iter()
+            .partition(|project| project.path.is_dir());
+        self.projects = existing;
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn record_and_prune_projects() -> Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let project = temp.child("my-project");
+        project.create_dir_all()?;
+        let registry_file = temp.child("projects.yml");
+
+        let mut registry = Registry::load(registry_file.path())?;
+        registry.record(project.path());
+        registry.record(project.path());
+        registry.record(&temp.path().join("deleted-project"));
+        registry.save(registry_file.path())?;
+
+        let mut registry = Registry::load(registry_file.path())?;
+        assert_eq!(registry.projects.len(), 2);
+        assert_eq!(registry.projects[0].name, "my-project");
+
+        let removed = registry.prune();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].name, "deleted-project");
+        assert_eq!(registry.projects.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn record_example_project() -> Result<()> {
+        let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/php-fpm-and-nginx");
+        let config_file = crate::utils::path::find_recursively(&example.join("src"), ".dev-cli.dist.yml").unwrap();
+
+        let mut registry = Registry::default();
+        registry.record(config_file.parent().unwrap());
+        assert_eq!(registry.projects[0].name, "php-fpm-and-nginx");
+        assert_eq!(registry.projects[0].path, example);
+        assert!(registry.prune().is_empty());
+        Ok(())
+    }
+}
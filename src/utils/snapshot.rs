@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use anyhow::{bail, Result};
+use crate::utils::database::{major_version, Engine};
+
+/// The metadata stored next to the dump of a snapshot
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub engine: Engine,
+    pub image: String,
+    /// Version of the database server the dump was created with
+    pub version: Option<String>,
+    /// UNIX timestamp of the creation
+    pub created_at: u64,
+    /// The commit checked out in the project when the snapshot was created
+    pub git_commit: Option<String>,
+}
+
+impl Snapshot {
+    /// The directory snapshots are stored in, within the dumps directory
+    pub fn directory(dumps_dir: &Path) -> PathBuf {
+        dumps_dir.join("snapshots")
+    }
+
+    pub fn dump_file(directory: &Path, name: &str) -> PathBuf {
+        directory.join(format!("{}.sql.gz", name))
+    }
+
+    fn metadata_file(directory: &Path, name: &str) -> PathBuf {
+        directory.join(format!("{}.yml", name))
+    }
+
+    pub fn load(directory: &Path, name: &str) -> Result<Option<Self>> {
+        let file = Self::metadata_file(directory, name);
+        if !file.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(serde_yaml::from_reader(File::open(file)?)?))
+    }
+
+    /// Loads all snapshots, the oldest first.
+    pub fn load_all(directory: &Path) -> Result<Vec<Self>> {
+        let mut snapshots = Vec::new();
+        if !directory.is_dir() {
+            return Ok(snapshots);
+        }
+
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("yml") {
+                continue;
+            }
+            let snapshot: Snapshot = serde_yaml::from_reader(File::open(&path)?)?;
+            snapshots.push(snapshot);
+        }
+        snapshots.sort_by_key(|snapshot| snapshot.created_at);
+
+        Ok(snapshots)
+    }
+
+    /// The snapshots to remove to keep only the newest `keep` ones, expecting
+    /// them ordered like `load_all` does.
+    pub fn outdated(snapshots: Vec<Self>, keep: usize) -> Vec<Self> {
+        let remove = snapshots.len().saturating_sub(keep);
+        snapshots.into_iter().take(remove).collect()
+    }
+
+    /// Checks that the snapshot can be restored into a database of the engine
+    /// and server version. Returns false if the versions are unknown and
+    /// couldn't be compared.
+    pub fn check_compatible(&self, engine: Engine, server_version: Option<&str>) -> Result<bool> {
+        if self.engine != engine {
+            bail!("The snapshot '{}' was created with {}, but the database is {}", self.name, self.engine, engine);
+        }
+        match (self.version.as_deref(), server_version) {
+            (Some(version), Some(server_version)) if major_version(version) != major_version(server_version) => bail!(
+                "The snapshot '{}' was created with {} {}, which is incompatible with the running {} {}",
+                self.name, self.engine, version, engine, server_version
+            ),
+            (Some(_), Some(_)) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    pub fn save(&self, directory: &Path) -> Result<()> {
+        serde_yaml::to_writer(File::create(Self::metadata_file(directory, &self.name))?, self)?;
+        Ok(())
+    }
+
+    /// Removes the dump and the metadata of the snapshot.
+    pub fn remove(&self, directory: &Path) -> Result<()> {
+        for file in [Self::dump_file(directory, &self.name), Self::metadata_file(directory, &self.name)] {
+            if file.is_file() {
+                std::fs::remove_file(file)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(name: &str, engine: Engine, version: Option<&str>, created_at: u64) -> Snapshot {
+        Snapshot {
+            name: String::from(name),
+            engine,
+            image: String::from("mysql:8.0"),
+            version: version.map(String::from),
+            created_at,
+            git_commit: Some(String::from("0123456789abcdef")),
+        }
+    }
+
+    #[test]
+    fn save_and_load() -> Result<()> {
+        let directory = assert_fs::TempDir::new()?;
+        snapshot("before-migration", Engine::Mysql, Some("8.0.36"), 1700000000).save(&directory)?;
+
+        let loaded = Snapshot::load(&directory, "before-migration")?.unwrap();
+        assert_eq!(loaded.name, "before-migration");
+        assert_eq!(loaded.engine, Engine::Mysql);
+        assert_eq!(loaded.image, "mysql:8.0");
+        assert_eq!(loaded.version.as_deref(), Some("8.0.36"));
+        assert_eq!(loaded.created_at, 1700000000);
+        assert_eq!(loaded.git_commit.as_deref(), Some("0123456789abcdef"));
+        assert!(Snapshot::load(&directory, "missing")?.is_none());
+
+        loaded.remove(&directory)?;
+        assert!(Snapshot::load(&directory, "before-migration")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn compatible_engines_and_versions() {
+        let snapshot = snapshot("snapshot", Engine::Postgres, Some("15.4"), 0);
+        assert!(snapshot.check_compatible(Engine::Postgres, Some("15.6")).unwrap());
+        assert!(snapshot.check_compatible(Engine::Postgres, Some("16.2")).is_err());
+        assert!(snapshot.check_compatible(Engine::Mysql, Some("15.4")).is_err());
+        assert!(!snapshot.check_compatible(Engine::Postgres, None).unwrap());
+
+        let unknown = self::snapshot("snapshot", Engine::Mariadb, None, 0);
+        assert!(!unknown.check_compatible(Engine::Mariadb, Some("11.2.2")).unwrap());
+    }
+
+    #[test]
+    fn prune_keeps_the_newest() -> Result<()> {
+        let directory = assert_fs::TempDir::new()?;
+        for (name, created_at) in [("b", 200), ("c", 300), ("a", 100), ("d", 400)] {
+            snapshot(name, Engine::Mysql, None, created_at).save(&directory)?;
+        }
+
+        let names = |snapshots: Vec<Snapshot>| snapshots.into_iter().map(|snapshot| snapshot.name).collect::<Vec<_>>();
+        assert_eq!(names(Snapshot::outdated(Snapshot::load_all(&directory)?, 2)), vec!["a", "b"]);
+        assert_eq!(names(Snapshot::outdated(Snapshot::load_all(&directory)?, 0)), vec!["a", "b", "c", "d"]);
+        assert!(Snapshot::outdated(Snapshot::load_all(&directory)?, 5).is_empty());
+        Ok(())
+    }
+}
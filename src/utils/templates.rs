@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+
+/// A set of files to start a new project with
+#[derive(Debug)]
+pub struct Template {
+    pub name: String,
+    pub description: String,
+    /// The files relative to the project root, with their content
+    pub files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+macro_rules! template_file {
+    ($template:literal, $file:literal) => {
+        (
+            PathBuf::from($file),
+            include_bytes!(concat!("../../examples/", $template, "/", $file)).to_vec(),
+        )
+    };
+}
+
+/// The templates shipped with dev-cli, built from the examples
+pub fn builtin() -> Vec<Template> {
+    vec![
+        Template {
+            name: String::from("static"),
+            description: String::from("A static website served by nginx"),
+            files: vec![
+                template_file!("static", "compose.yml"),
+                template_file!("static", ".dev-cli.dist.yml"),
+            ],
+        },
+        Template {
+            name: String::from("php-fpm-and-nginx"),
+            description: String::from("A PHP application running in PHP-FPM behind nginx"),
+            files: vec![
+                template_file!("php-fpm-and-nginx", "compose.yml"),
+                template_file!("php-fpm-and-nginx", ".dev-cli.dist.yml"),
+                template_file!("php-fpm-and-nginx", "conf/site.conf"),
+                template_file!("php-fpm-and-nginx", "src/index.php"),
+            ],
+        },
+        Template {
+            name: String::from("use-subfolder"),
+            description: String::from("A static website, showing that dev-cli works from subfolders"),
+            files: vec![
+                template_file!("use-subfolder", "compose.yml"),
+                template_file!("use-subfolder", ".dev-cli.dist.yml"),
+                template_file!("use-subfolder", "some-folder/try-running-me-from-here.txt"),
+            ],
+        },
+    ]
+}
+
+/// Loads the templates defined by the user, every directory in the given
+/// directory is a template containing all files in it.
+pub fn user_defined(templates_dir: &Path) -> std::io::Result<Vec<Template>> {
+    let mut templates = Vec::new();
+    if !templates_dir.is_dir() {
+        return Ok(templates);
+    }
+
+    for entry in std::fs::read_dir(templates_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let mut files = Vec::new();
+        collect_files(&path, &path, &mut files)?;
+        templates.push(Template {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            description: format!("Custom template from {}", path.display()),
+            files,
+        });
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(templates)
+}
+
+fn collect_files(root: &Path, directory: &Path, files: &mut Vec<(PathBuf, Vec<u8>)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            files.push((path.strip_prefix(root).unwrap().to_path_buf(), std::fs::read(&path)?));
+        }
+    }
+    Ok(())
+}
+
+/// All available templates, user defined ones replace built-in ones with the same name.
+pub fn all(templates_dir: &Path) -> std::io::Result<Vec<Template>> {
+    let user_defined = user_defined(templates_dir)?;
+    let mut templates: Vec<Template> = builtin()
+        .into_iter()
+        .filter(|template| !user_defined.iter().any(|user_template| user_template.name == template.name))
+        .collect();
+    templates.extend(user_defined);
+    Ok(templates)
+}
@@ -0,0 +1,18 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The current time as a UNIX timestamp
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Describes how long ago a UNIX timestamp was, e.g. "5 minutes ago".
+pub fn ago(timestamp: u64) -> String {
+    let seconds = now().saturating_sub(timestamp);
+    let (amount, unit) = match seconds {
+        0..=59 => return String::from("just now"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    format!("{} {}{} ago", amount, unit, if amount == 1 { "" } else { "s" })
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+const ROUTER_LABEL_PREFIX: &str = "traefik.http.routers.";
+
+/// The TLD used when the `TLD` environment variable isn't set. Browsers
+/// resolve every `*.localhost` host name to the local machine.
+pub const DEFAULT_TLD: &str = "localhost";
+
+/// A host name Traefik routes to a service
+#[derive(Debug, PartialEq)]
+pub struct Route {
+    pub router: String,
+    pub host: String,
+    pub tls: bool,
+}
+
+impl Route {
+    pub fn url(&self) -> String {
+        format!("{}://{}/", if self.tls { "https" } else { "http" }, self.host)
+    }
+}
+
+/// Returns the value of the `TLD` environment variable used in the labels.
+pub fn tld() -> String {
+    std::env::var("TLD")
+        .ok()
+        .filter(|tld| !tld.is_empty())
+        .unwrap_or(String::from(DEFAULT_TLD))
+}
+
+/// Replaces the variables used in the labels of the examples, in case they
+/// haven't been interpolated by docker compose already.
+pub fn resolve(value: &str, project_name: &str) -> String {
+    value
+        .replace("${COMPOSE_PROJECT_NAME}", project_name)
+        .replace("${TLD}", &tld())
+}
+
+/// Collects the host names of all routers defined in the labels of a service.
+pub fn routes(labels: &BTreeMap<String, String>, project_name: &str) -> Vec<Route> {
+    let mut routes = Vec::new();
+    for (label, rule) in labels {
+        let router = match label
+            .strip_prefix(ROUTER_LABEL_PREFIX)
+            .and_then(|label| label.strip_suffix(".rule"))
+        {
+            Some(router) => router,
+            None => continue,
+        };
+
+        let tls_label = format!("{}{}.tls", ROUTER_LABEL_PREFIX, router);
+        let tls = labels.get(&tls_label).map(|tls| tls == "true").unwrap_or(false)
+            || labels.keys().any(|label| label.starts_with(&format!("{}.", tls_label)));
+
+        for host in hosts(&resolve(rule, project_name)) {
+            routes.push(Route {
+                router: resolve(router, project_name),
+                host,
+                tls,
+            });
+        }
+    }
+
+    routes
+}
+
+/// Extracts the host names of all `Host(...)` matchers in a router rule.
+fn hosts(rule: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for (index, _) in rule.match_indices("Host(") {
+        // Skip matchers that only end in "Host", e.g. "ClientHost("
+        let preceding = rule[..index].chars().last();
+        if preceding.map(|char| char.is_alphanumeric()).unwrap_or(false) {
+            continue;
+        }
+
+        let arguments = &rule[index + "Host(".len()..];
+        let arguments = &arguments[..arguments.find(')').unwrap_or(arguments.len())];
+        for argument in arguments.split(',') {
+            let host = argument.trim().trim_matches(|char| char == '`' || char == '"' || char == '\'');
+            if host.is_empty() {
+                continue;
+            }
+            // An empty TLD variable leaves a trailing dot behind
+            let host = match host.strip_suffix('.') {
+                Some(host) => format!("{}.{}", host, tld()),
+                None => host.to_string(),
+            };
+            hosts.push(host);
+        }
+    }
+
+    hosts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_from_example_labels() {
+        let labels: BTreeMap<String, String> = [
+            ("traefik.http.routers.${COMPOSE_PROJECT_NAME}0.rule", "Host(`${COMPOSE_PROJECT_NAME}.${TLD}`)"),
+            ("traefik.http.routers.${COMPOSE_PROJECT_NAME}0.middlewares", "redirect-to-https"),
+            ("traefik.http.routers.${COMPOSE_PROJECT_NAME}.rule", "Host(`${COMPOSE_PROJECT_NAME}.${TLD}`)"),
+            ("traefik.http.routers.${COMPOSE_PROJECT_NAME}.tls", "true"),
+        ].into_iter().map(|(label, value)| (label.to_string(), value.to_string())).collect();
+
+        let routes = routes(&labels, "static");
+        assert_eq!(routes.len(), 2);
+        let host = format!("static.{}", tld());
+        assert_eq!(routes[0], Route { router: String::from("static"), host: host.clone(), tls: true });
+        assert_eq!(routes[1], Route { router: String::from("static0"), host: host.clone(), tls: false });
+        assert_eq!(routes[0].url(), format!("https://{}/", host));
+    }
+
+    #[test]
+    fn hosts_from_rules() {
+        assert_eq!(hosts("Host(`a.test`, `b.test`) || Host(\"c.test\")"), vec!["a.test", "b.test", "c.test"]);
+        assert_eq!(hosts("HostRegexp(`{any:.+}`) && PathPrefix(`/api`)"), Vec::<String>::new());
+        assert_eq!(hosts("Host(`project.`)"), vec![format!("project.{}", tld())]);
+    }
+}
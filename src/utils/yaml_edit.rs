@@ -0,0 +1,236 @@
+use serde_yaml::Value;
+
+/// Sets the value at a path of keys in a YAML document, e.g.
+/// `["services", "php", "user"]`. The document is edited line by line, so the
+/// comments and formatting of everything else stay untouched. Missing keys are
+/// appended to the end of their parent mapping.
+pub fn set(content: &str, path: &[&str], value: &Value) -> String {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    // The lines of the mapping the current key is searched in
+    let mut start = 0;
+    let mut end = lines.len();
+    let mut parent_indent: Option<usize> = None;
+
+    for (depth, key) in path.iter().enumerate() {
+        let block_indent = (start..end).find(|&n| is_significant(&lines[n])).map(|n| indent_of(&lines[n]));
+        let found = (start..end).find(|&n| {
+            is_significant(&lines[n])
+                && Some(indent_of(&lines[n])) == block_indent
+                && parse_key(&lines[n]).map(|(found, _)| found == *key).unwrap_or(false)
+        });
+
+        let n = match found {
+            Some(n) => n,
+            None => {
+                // Append the missing keys after the last line of the mapping
+                let indent = block_indent.unwrap_or(parent_indent.map(|indent| indent + 2).unwrap_or(0));
+                let at = (start..end).rev().find(|&n| is_significant(&lines[n])).map(|n| n + 1).unwrap_or(start);
+                let mut new_lines = Vec::new();
+                for (offset, key) in path[depth..].iter().enumerate() {
+                    let indent = indent + offset * 2;
+                    if depth + offset + 1 == path.len() {
+                        new_lines.extend(value_lines(key, value, indent, ""));
+                    } else {
+                        new_lines.push(format!("{}{}:", " ".repeat(indent), key));
+                    }
+                }
+                lines.splice(at..at, new_lines);
+                return to_string(lines, content);
+            }
+        };
+
+        let indent = indent_of(&lines[n]);
+        let mut children_end = children_end(&lines, n, end);
+        let comment = parse_key(&lines[n]).map(|(_, rest)| comment_of(rest).to_string()).unwrap_or_default();
+        if depth + 1 == path.len() {
+            lines.splice(n..children_end, value_lines(key, value, indent, &comment));
+            return to_string(lines, content);
+        }
+
+        // A flow mapping like `{user: root}` is converted to the block style,
+        // so the key can be added to it. A scalar can't have any children, so
+        // it is replaced by a mapping.
+        let inline_value = parse_key(&lines[n])
+            .map(|(_, rest)| rest[..rest.len() - comment.len()].trim().to_string())
+            .unwrap_or_default();
+        if !inline_value.is_empty() {
+            let block = match serde_yaml::from_str::<Value>(&inline_value) {
+                Ok(mapping @ Value::Mapping(_)) if mapping.as_mapping().is_some_and(|mapping| !mapping.is_empty()) => {
+                    value_lines(key, &mapping, indent, &comment)
+                }
+                _ => vec![format!("{}{}:{}", " ".repeat(indent), key, comment)],
+            };
+            let added = block.len() - 1;
+            lines.splice(n..n + 1, block);
+            children_end += added;
+        }
+        start = n + 1;
+        end = children_end;
+        parent_indent = Some(indent);
+    }
+
+    to_string(lines, content)
+}
+
+fn to_string(lines: Vec<String>, original: &str) -> String {
+    let mut content = lines.join("\n");
+    if original.is_empty() || original.ends_with('\n') {
+        content.push('\n');
+    }
+    content
+}
+
+/// Lines holding content, not only whitespace, a comment or a document marker
+fn is_significant(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---"
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Splits a `key: value` line into the key and everything after the colon.
+fn parse_key(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('-') || trimmed.starts_with('#') {
+        return None;
+    }
+
+    if let Some(quote) = trimmed.chars().next().filter(|char| *char == '"' || *char == '\'') {
+        let closing = trimmed[1..].find(quote)? + 1;
+        let rest = trimmed[closing + 1..].strip_prefix(':')?;
+        return Some((&trimmed[1..closing], rest));
+    }
+
+    let colon = trimmed
+        .match_indices(':')
+        .map(|(index, _)| index)
+        .find(|&index| trimmed[index + 1..].is_empty() || trimmed[index + 1..].starts_with(char::is_whitespace))?;
+    Some((&trimmed[..colon], &trimmed[colon + 1..]))
+}
+
+/// The trailing comment of a value, including the whitespace before it
+fn comment_of(value: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, char) in value.char_indices() {
+        match (quote, char) {
+            (None, '"' | '\'') => quote = Some(char),
+            (Some(open), _) if open == char => quote = None,
+            (None, '#') if previous.is_whitespace() => {
+                let start = value[..index].trim_end().len();
+                return &value[start..];
+            }
+            _ => {}
+        }
+        previous = char;
+    }
+    ""
+}
+
+/// The end of the lines belonging to the value of the key at line `n`. Lines
+/// of a sequence may have the same indentation as their key.
+fn children_end(lines: &[String], n: usize, end: usize) -> usize {
+    let indent = indent_of(&lines[n]);
+    let mut children_end = n + 1;
+    for (index, line) in lines.iter().enumerate().take(end).skip(n + 1) {
+        if !is_significant(line) {
+            continue;
+        }
+        let line_indent = indent_of(line);
+        if line_indent < indent || (line_indent == indent && !line.trim_start().starts_with('-')) {
+            break;
+        }
+        children_end = index + 1;
+    }
+    children_end
+}
+
+fn value_lines(key: &str, value: &Value, indent: usize, comment: &str) -> Vec<String> {
+    let padding = " ".repeat(indent);
+    let serialized = serde_yaml::to_string(value).unwrap_or_default();
+    let is_block = match value {
+        Value::Mapping(mapping) => !mapping.is_empty(),
+        Value::Sequence(sequence) => !sequence.is_empty(),
+        _ => false,
+    };
+
+    if !is_block {
+        return vec![format!("{}{}: {}{}", padding, key, serialized.trim_end(), comment)];
+    }
+    let mut lines = vec![format!("{}{}:{}", padding, key, comment)];
+    lines.extend(serialized.lines().map(|line| format!("{}  {}", padding, line)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+# The database service
+database_container: db # used by export-db
+
+services:
+  php:
+    user: www-data
+    # Always use bash
+    shell: bash
+  node: {}
+run-commands:
+  versions:
+    commands:
+    - container: php
+      command: php --version
+";
+
+    fn value(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn replace_value_keeping_comments() {
+        let content = set(CONTENT, &["database_container"], &value("mysql"));
+        assert!(content.starts_with("# The database service\ndatabase_container: mysql # used by export-db\n\nservices:"));
+
+        let content = set(CONTENT, &["services", "php", "user"], &value("root"));
+        assert!(content.contains("  php:\n    user: root\n    # Always use bash\n    shell: bash\n"));
+    }
+
+    #[test]
+    fn add_missing_keys() {
+        let content = set(CONTENT, &["services", "php", "workdir"], &value("/app"));
+        assert!(content.contains("    shell: bash\n    workdir: /app\n  node: {}\n"));
+
+        let content = set(CONTENT, &["services", "node", "user"], &value("node"));
+        assert!(content.contains("  node:\n    user: node\nrun-commands:"));
+
+        let content = set(CONTENT, &["launch_url"], &value("https://app.localhost"));
+        assert!(content.ends_with("      command: php --version\nlaunch_url: https://app.localhost\n"));
+
+        assert_eq!(set("", &["services", "php", "user"], &value("root")), "services:\n  php:\n    user: root\n");
+    }
+
+    #[test]
+    fn replace_blocks() {
+        let content = set(CONTENT, &["run-commands", "versions", "commands"], &value("[]"));
+        assert!(content.ends_with("  versions:\n    commands: []\n"));
+
+        let content = set(CONTENT, &["services", "php"], &value("{user: root}"));
+        assert!(content.contains("services:\n  php:\n    user: root\n  node: {}\n"));
+    }
+
+    #[test]
+    fn add_keys_to_flow_mappings() {
+        let content = "services:\n  php: {user: root} # the app\n  node: {}\n";
+        assert_eq!(
+            set(content, &["services", "php", "shell"], &value("bash")),
+            "services:\n  php: # the app\n    user: root\n    shell: bash\n  node: {}\n"
+        );
+        assert_eq!(
+            set(content, &["services", "node", "user"], &value("node")),
+            "services:\n  php: {user: root} # the app\n  node:\n    user: node\n"
+        );
+    }
+}
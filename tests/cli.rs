@@ -5,12 +5,111 @@ use assert_fs::prelude::*;
 
 #[test]
 fn illegal_config() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("dev-cli")?;
     let file = assert_fs::NamedTempFile::new(".dev-cli.yml")?;
     file.write_str("InVaLId YaML $$$")?;
 
-    cmd.assert()
+    Command::cargo_bin("dev-cli")?
+        .current_dir(file.path().parent().unwrap())
+        .args(["launch", "--print"])
+        .assert()
         .failure()
-        .stdout(predicate::str::contains("Docker"));
+        .stderr(predicate::str::contains("Could not parse").and(predicate::str::contains(".dev-cli.yml")));
+    Ok(())
+}
+
+#[test]
+fn init_from_template() -> Result<(), Box<dyn std::error::Error>> {
+    let project = assert_fs::TempDir::new()?;
+    let config_home = assert_fs::TempDir::new()?;
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .args(["init", "--template", "php-fpm-and-nginx"])
+        .assert()
+        .success();
+    project.child("compose.yml").assert(predicate::str::contains("php:8.2-fpm"));
+    project.child(".dev-cli.dist.yml").assert(predicate::path::exists());
+    project.child("conf/site.conf").assert(predicate::path::exists());
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .args(["init", "--template", "static"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .args(["init", "--template", "static", "--force"])
+        .assert()
+        .success();
+    project.child("compose.yml").assert(predicate::str::contains("image: nginx"));
+    Ok(())
+}
+
+#[test]
+fn config_set_and_get() -> Result<(), Box<dyn std::error::Error>> {
+    let project = assert_fs::TempDir::new()?;
+    let config_home = assert_fs::TempDir::new()?;
+    project.child(".dev-cli.dist.yml").write_str("# Shared settings\ndatabase_container: mysql # the db\n")?;
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .args(["config", "set", "--project", "database_container", "mariadb"])
+        .assert()
+        .success();
+    project.child(".dev-cli.dist.yml").assert("# Shared settings\ndatabase_container: mariadb # the db\n");
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .args(["config", "set", "services.php.user", "www-data"])
+        .assert()
+        .success();
+    project.child(".dev-cli.yml").assert("services:\n  php:\n    user: www-data\n");
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .args(["config", "get", "services.php.user"])
+        .assert()
+        .success()
+        .stdout("www-data\n");
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .env("DEV_CLI_DUMPS_DIR", "backups")
+        .args(["config", "show", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dumps_dir: backups  # DEV_CLI_DUMPS_DIR"));
+    Ok(())
+}
+
+#[test]
+fn config_validate() -> Result<(), Box<dyn std::error::Error>> {
+    let project = assert_fs::TempDir::new()?;
+    let config = project.child(".dev-cli.dist.yml");
+    config.write_str("databse_container: db\n")?;
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project.path())
+        .env("XDG_CONFIG_HOME", project.path())
+        .args(["config", "validate"])
+        .assert()
+        .code(78)
+        .stderr(predicate::str::contains(".dev-cli.dist.yml:1:1: unknown field `databse_container`, did you mean `database_container`?"));
+
+    config.write_str("database_container: db\n")?;
+    Command::cargo_bin("dev-cli")?
+        .args(["config", "validate"])
+        .arg(config.path())
+        .assert()
+        .success();
     Ok(())
 }